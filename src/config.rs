@@ -0,0 +1,189 @@
+use crate::{default_output_style, SassError};
+use rocket::{
+    figment::{value::magic::RelativePathBuf, Figment},
+    serde::{de::DeserializeOwned, Deserialize},
+    warn,
+};
+use std::{collections::HashMap, time::Duration};
+
+pub(crate) const DEFAULT_MAX_AGE: u64 = 86400;
+/// RFC 9111 has caches treat any larger `max-age` as this one, which fits in 32 bits.
+const MAX_DELTA_SECONDS: u64 = 1 << 31;
+const MAX_RETRY_BACKOFF: Duration = Duration::from_secs(60);
+pub(crate) const DEFAULT_SHEET_PATH: &str = "assets/style.scss";
+pub(crate) const DEFAULT_MAX_IMPORT_DEPTH: usize = 64;
+const DEFAULT_BANNER: &str = "Compiled by rocket-sass-fairing from {file}, version {version}";
+
+/// The fairing's settings, as extracted from Rocket's configuration.
+///
+/// Each field mirrors the key of the same name (see the [crate docs](crate#configuration)),
+/// with its default already applied. See [`SassSheetFairing::configure`] to change them
+/// before the sheet is compiled.
+///
+/// [`SassSheetFairing::configure`]: crate::SassSheetFairing::configure
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub struct SassConfig {
+    /// `sass_sheet_path`; `None` compiles `assets/style.scss`.
+    pub sheet_path: Option<RelativePathBuf>,
+    /// `assets_max_age`, in seconds.
+    pub max_age: u64,
+    /// `sass_output_style`.
+    pub output_style: grass::OutputStyle,
+    /// `sass_compile_timeout`, in milliseconds; `None` lets compilation take as long as it needs.
+    pub compile_timeout: Option<Duration>,
+    /// `sass_follow_symlinks`.
+    pub follow_symlinks: bool,
+    /// `sass_indent`, as the string each level is indented with.
+    pub indent: String,
+    /// `sass_passthrough`; `None` serves `.css` files as-is and compiles anything else.
+    pub passthrough: Option<bool>,
+    /// `sass_read_retries`.
+    pub read_retries: u32,
+    /// `sass_read_retry_delay`, before the first retry; see [`SassConfig::read_retry_backoff`].
+    pub read_retry_delay: Duration,
+    /// `sass_strict`.
+    pub strict: bool,
+    /// `sass_url_rewrites`.
+    pub url_rewrites: HashMap<String, String>,
+    /// `sass_dump_to_stderr`.
+    pub dump_to_stderr: bool,
+    /// `sass_maintenance_path`.
+    pub maintenance_path: Option<RelativePathBuf>,
+    /// `sass_max_import_depth`.
+    pub max_import_depth: usize,
+    /// `sass_banner`, as the template to render; `None` adds no banner.
+    pub banner: Option<String>,
+}
+
+impl SassConfig {
+    /// Extracts the settings from `figment`, failing on the first invalid one.
+    pub fn from_figment(figment: &Figment) -> Result<Self, SassError> {
+        let output_style = extract_or::<Option<OutputStyle>>(figment, "sass_output_style", None)?
+            .map_or_else(default_output_style, grass::OutputStyle::from);
+
+        let indent = match extract_or(figment, "sass_indent", Indent::Width(2))? {
+            Indent::Width(width) => " ".repeat(width),
+            Indent::Named(name) if name == "tab" => "\t".to_owned(),
+            Indent::Named(name) => {
+                let message = format!(
+                    "invalid sass_indent '{}': expected a number of spaces or 'tab'",
+                    name
+                );
+                return Err(SassError::Config(Box::new(message.into())));
+            }
+        };
+
+        let banner = match extract_or(figment, "sass_banner", Banner::Enabled(false))? {
+            Banner::Enabled(true) => Some(DEFAULT_BANNER.to_owned()),
+            Banner::Enabled(false) => None,
+            Banner::Template(template) => Some(template),
+        };
+
+        Ok(SassConfig {
+            sheet_path: extract_or(figment, "sass_sheet_path", None)?,
+            max_age: extract_or(figment, "assets_max_age", DEFAULT_MAX_AGE)?,
+            output_style,
+            compile_timeout: extract_or::<Option<u64>>(figment, "sass_compile_timeout", None)?
+                .map(Duration::from_millis),
+            follow_symlinks: extract_or(figment, "sass_follow_symlinks", true)?,
+            indent,
+            passthrough: extract_or(figment, "sass_passthrough", None)?,
+            read_retries: extract_or(figment, "sass_read_retries", 0)?,
+            read_retry_delay: Duration::from_millis(extract_or(
+                figment,
+                "sass_read_retry_delay",
+                250,
+            )?),
+            strict: extract_or(figment, "sass_strict", false)?,
+            url_rewrites: extract_or(figment, "sass_url_rewrites", HashMap::new())?,
+            dump_to_stderr: extract_or(figment, "sass_dump_to_stderr", false)?,
+            maintenance_path: extract_or(figment, "sass_maintenance_path", None)?,
+            max_import_depth: extract_or(
+                figment,
+                "sass_max_import_depth",
+                DEFAULT_MAX_IMPORT_DEPTH,
+            )?,
+            banner,
+        })
+    }
+
+    /// How long to wait before retrying to open the sheet after `attempt` retries:
+    /// `read_retry_delay`, doubled after each retry up to a minute, or to `read_retry_delay`
+    /// itself when it's longer.
+    /// ```rust
+    /// use rocket_sass_fairing::SassConfig;
+    /// use std::time::Duration;
+    ///
+    /// let figment = rocket::Config::figment().merge(("sass_read_retry_delay", 250));
+    /// let config = SassConfig::from_figment(&figment).unwrap();
+    /// assert_eq!(config.read_retry_backoff(0), Duration::from_millis(250));
+    /// assert_eq!(config.read_retry_backoff(2), Duration::from_secs(1));
+    /// assert_eq!(config.read_retry_backoff(u32::MAX), Duration::from_secs(60));
+    /// # let figment = rocket::Config::figment().merge(("sass_read_retry_delay", u64::MAX));
+    /// # let config = SassConfig::from_figment(&figment).unwrap();
+    /// # assert_eq!(config.read_retry_backoff(0), Duration::from_millis(u64::MAX));
+    /// # assert_eq!(config.read_retry_backoff(40), Duration::from_millis(u64::MAX));
+    /// ```
+    pub fn read_retry_backoff(&self, attempt: u32) -> Duration {
+        let cap = MAX_RETRY_BACKOFF.max(self.read_retry_delay);
+        2u32.checked_pow(attempt)
+            .and_then(|factor| self.read_retry_delay.checked_mul(factor))
+            .map_or(cap, |backoff| backoff.min(cap))
+    }
+
+    /// `max_age`, capped at the largest value caches are required to handle.
+    pub(crate) fn capped_max_age(&self) -> u64 {
+        if self.max_age > MAX_DELTA_SECONDS {
+            warn!(
+                "`assets_max_age` of {}s is more than caches handle, using {}s instead.",
+                self.max_age, MAX_DELTA_SECONDS
+            );
+            return MAX_DELTA_SECONDS;
+        }
+        self.max_age
+    }
+}
+
+#[derive(Deserialize, Clone, Copy)]
+#[serde(crate = "rocket::serde", rename_all = "lowercase")]
+enum OutputStyle {
+    Expanded,
+    Compressed,
+}
+
+impl From<OutputStyle> for grass::OutputStyle {
+    fn from(style: OutputStyle) -> Self {
+        match style {
+            OutputStyle::Expanded => grass::OutputStyle::Expanded,
+            OutputStyle::Compressed => grass::OutputStyle::Compressed,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(crate = "rocket::serde", untagged)]
+enum Indent {
+    Width(usize),
+    Named(String),
+}
+
+#[derive(Deserialize)]
+#[serde(crate = "rocket::serde", untagged)]
+enum Banner {
+    Enabled(bool),
+    Template(String),
+}
+
+/// Extracts `key` from the configuration, falling back to `default` when it isn't set.
+pub(crate) fn extract_or<T: DeserializeOwned>(
+    figment: &Figment,
+    key: &str,
+    default: T,
+) -> Result<T, SassError> {
+    match figment.extract_inner(key) {
+        Ok(value) => Ok(value),
+        Err(e) if e.missing() => Ok(default),
+        Err(e) => Err(SassError::Config(Box::new(e))),
+    }
+}
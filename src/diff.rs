@@ -0,0 +1,166 @@
+use crate::{scan, SassSheet};
+use std::{collections::HashMap, fmt};
+
+/// Coarse summary of how compiled CSS changed between two sheets, by top-level rule block.
+///
+/// Blocks are keyed by their prelude (a selector list, or an at-rule such as `@media screen`)
+/// with whitespace collapsed, so the same sheet compiled with another output style compares
+/// equal. Nested blocks, like the rules inside a `@media`, count as part of their parent.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct CssDiff {
+    /// Preludes of the blocks only found in the newer sheet.
+    pub added: Vec<String>,
+    /// Preludes of the blocks only found in the older sheet.
+    pub removed: Vec<String>,
+    /// Preludes of the blocks found in both, with different contents.
+    pub changed: Vec<String>,
+    /// Size of the newer sheet minus the size of the older one, in bytes.
+    pub size_delta: i64,
+}
+
+impl CssDiff {
+    /// Whether no block was added, removed or changed.
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+}
+
+impl fmt::Display for CssDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{:+} bytes", self.size_delta)?;
+        for (sign, preludes) in [
+            ("+", &self.added),
+            ("-", &self.removed),
+            ("~", &self.changed),
+        ] {
+            for prelude in preludes {
+                writeln!(f, "{} {}", sign, prelude)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl SassSheet {
+    /// Summarizes the changes from this sheet to `other`.
+    /// ```rust
+    /// use rocket_sass_fairing::{default_options, SassSheet};
+    ///
+    /// let compile = |scss: &str| SassSheet::from_reader(scss.as_bytes(), &default_options());
+    /// let old = compile("a { color: red } b { color: red }").unwrap();
+    /// let new = compile("a { color: blue } i { color: red }").unwrap();
+    ///
+    /// let diff = old.diff(&new);
+    /// assert_eq!(diff.added, ["i"]);
+    /// assert_eq!(diff.removed, ["b"]);
+    /// assert_eq!(diff.changed, ["a"]);
+    /// assert_eq!(diff.size_delta, 1);
+    /// assert!(old.diff(&old).is_empty());
+    /// ```
+    pub fn diff(&self, other: &SassSheet) -> CssDiff {
+        // Each guard is dropped before taking the next, as re-locking the same sheet while holding
+        // its guard can deadlock behind a queued writer.
+        let old_content = self.current().content.clone();
+        let new_content = other.current().content.clone();
+        let old_blocks = blocks(&old_content);
+        let new_blocks = blocks(&new_content);
+        let old: HashMap<_, _> = old_blocks.iter().map(|(key, body)| (key, body)).collect();
+        let new: HashMap<_, _> = new_blocks.iter().map(|(key, body)| (key, body)).collect();
+
+        let mut diff = CssDiff {
+            size_delta: new_content.len() as i64 - old_content.len() as i64,
+            ..CssDiff::default()
+        };
+        for (key, body) in &new_blocks {
+            match old.get(key) {
+                None => diff.added.push(key.0.clone()),
+                Some(&old_body) if old_body != body => diff.changed.push(key.0.clone()),
+                Some(_) => {}
+            }
+        }
+        for (key, _) in &old_blocks {
+            if !new.contains_key(key) {
+                diff.removed.push(key.0.clone());
+            }
+        }
+
+        diff
+    }
+}
+
+/// Splits `css` into its top-level blocks, keyed by prelude and occurrence so a selector
+/// repeated further down the sheet is compared with its counterpart.
+fn blocks(css: &str) -> Vec<((String, usize), String)> {
+    let mut blocks = Vec::new();
+    let mut occurrences: HashMap<String, usize> = HashMap::new();
+    let mut depth = 0;
+    let mut start = 0;
+    let mut prelude_end = 0;
+
+    let mut chars = css.char_indices().peekable();
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '"' | '\'' => scan::skip_string(&mut chars, c),
+            '/' => {
+                let comment = scan::skip_comment(&mut chars, false);
+                if comment && depth == 0 {
+                    start = chars.peek().map_or(css.len(), |&(i, _)| i);
+                }
+            }
+            '{' => {
+                if depth == 0 {
+                    prelude_end = i;
+                }
+                depth += 1;
+            }
+            '}' if depth > 0 => {
+                depth -= 1;
+                if depth == 0 {
+                    let prelude = collapse_whitespace(&css[start..prelude_end]);
+                    let body = collapse_whitespace(&css[prelude_end + 1..i]);
+                    push_block(&mut blocks, &mut occurrences, prelude, body);
+                    start = i + 1;
+                }
+            }
+            ';' if depth == 0 => {
+                let statement = collapse_whitespace(&css[start..i]);
+                push_block(&mut blocks, &mut occurrences, statement, String::new());
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+
+    blocks
+}
+
+fn push_block(
+    blocks: &mut Vec<((String, usize), String)>,
+    occurrences: &mut HashMap<String, usize>,
+    prelude: String,
+    body: String,
+) {
+    let occurrence = occurrences.entry(prelude.clone()).or_insert(0);
+    blocks.push(((prelude, *occurrence), body));
+    *occurrence += 1;
+}
+
+/// Collapses whitespace runs to a single space, dropping the ones around punctuation so
+/// expanded and compressed output compare equal.
+fn collapse_whitespace(css: &str) -> String {
+    let mut collapsed = String::with_capacity(css.len());
+    let mut pending_space = false;
+    for c in css.trim().chars() {
+        if c.is_whitespace() {
+            pending_space = true;
+            continue;
+        }
+        let punctuation = |c: char| matches!(c, ':' | ';' | ',' | '{' | '}' | '>' | '+' | '~');
+        if pending_space && !punctuation(c) && !collapsed.ends_with(punctuation) {
+            collapsed.push(' ');
+        }
+        pending_space = false;
+        collapsed.push(c);
+    }
+    collapsed.trim_end_matches(';').to_owned()
+}
@@ -0,0 +1,18 @@
+/// Re-indents grass's expanded output, which nests by two spaces, with `indent` per level.
+///
+/// Leftover odd spaces, as found in multi-line comments, are kept after the new indentation.
+pub(crate) fn reindent(css: &str, indent: &str) -> String {
+    let mut reindented = String::with_capacity(css.len());
+    for line in css.split_inclusive('\n') {
+        let content = line.trim_start_matches(' ');
+        let spaces = line.len() - content.len();
+        for _ in 0..spaces / 2 {
+            reindented.push_str(indent);
+        }
+        if spaces % 2 == 1 {
+            reindented.push(' ');
+        }
+        reindented.push_str(content);
+    }
+    reindented
+}
@@ -0,0 +1,200 @@
+use crate::{scan, SassError};
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+/// Follows the `@import`, `@use` and `@forward` rules of the sheet at `path`, failing if they
+/// nest more than `max_depth` files deep, e.g. because of circular imports.
+///
+/// Only what grass would read from the filesystem is followed: imports it can't resolve are left
+/// for it to report, and so are files that can't be read.
+pub(crate) fn check_import_depth(path: &Path, max_depth: usize) -> Result<(), SassError> {
+    let source = fs::read_to_string(path).map_err(SassError::Io)?;
+    let dir = path.parent().unwrap_or_else(|| Path::new(""));
+    check_source(&source, dir, is_indented(path), max_depth)
+}
+
+/// Like [`check_import_depth`], for an in-memory `source` importing relative to `dir`.
+pub(crate) fn check_source(
+    source: &str,
+    dir: &Path,
+    indented: bool,
+    max_depth: usize,
+) -> Result<(), SassError> {
+    let mut visited = HashMap::new();
+    for import in imports(source, indented) {
+        if let Some(import) = resolve(dir, &import) {
+            visit(&import, 1, max_depth, &mut visited)?;
+        }
+    }
+    Ok(())
+}
+
+/// Visits the file at `path`, found `depth` imports deep. `visited` remembers the shallowest
+/// depth each file was fully checked at, as anything it imports is then known to fit. Files are
+/// only recorded once done, so a cycle keeps nesting until it's too deep.
+fn visit(
+    path: &Path,
+    depth: usize,
+    max_depth: usize,
+    visited: &mut HashMap<PathBuf, usize>,
+) -> Result<(), SassError> {
+    if depth > max_depth {
+        return Err(SassError::ImportTooDeep {
+            path: path.to_path_buf(),
+            max_depth,
+        });
+    }
+    if visited.get(path).is_some_and(|&seen| seen <= depth) {
+        return Ok(());
+    }
+    let source = match fs::read_to_string(path) {
+        Ok(source) => source,
+        Err(_) => return Ok(()),
+    };
+    let dir = path.parent().unwrap_or_else(|| Path::new(""));
+    for import in imports(&source, is_indented(path)) {
+        if let Some(import) = resolve(dir, &import) {
+            visit(&import, depth + 1, max_depth, visited)?;
+        }
+    }
+    visited.insert(path.to_path_buf(), depth);
+    Ok(())
+}
+
+/// The sheet at `path` and every file it loads, directly or not, in the order they're found.
+#[cfg(feature = "report")]
+pub(crate) fn reachable(path: &Path) -> Vec<PathBuf> {
+    let mut found = vec![path.to_path_buf()];
+    let mut i = 0;
+    while let Some(path) = found.get(i).cloned() {
+        i += 1;
+        let source = match fs::read_to_string(&path) {
+            Ok(source) => source,
+            Err(_) => continue,
+        };
+        let dir = path.parent().unwrap_or_else(|| Path::new(""));
+        for import in imports(&source, is_indented(&path)) {
+            match resolve(dir, &import) {
+                Some(import) if !found.contains(&import) => found.push(import),
+                _ => {}
+            }
+        }
+    }
+    found
+}
+
+fn is_indented(path: &Path) -> bool {
+    path.extension().is_some_and(|ext| ext == "sass")
+}
+
+/// The URLs of the sheets `source` loads, skipping comments and plain CSS imports.
+fn imports(source: &str, indented: bool) -> Vec<String> {
+    let mut imports = Vec::new();
+    let mut chars = source.char_indices().peekable();
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '"' | '\'' => scan::skip_string(&mut chars, c),
+            '/' => {
+                scan::skip_comment(&mut chars, true);
+            }
+            '@' => {
+                let rule: String = source[i + 1..]
+                    .chars()
+                    .take_while(|c| c.is_ascii_alphabetic())
+                    .collect();
+                if !matches!(rule.as_str(), "import" | "use" | "forward") {
+                    continue;
+                }
+                let start = i + 1 + rule.len();
+                let end = rule_end(&source[start..], indented).map_or(source.len(), |e| start + e);
+                let mut urls = quoted(&source[start..end]);
+                // Only the first string of a `@use` or `@forward` names the module, any others
+                // configure it.
+                if rule != "import" {
+                    urls.truncate(1);
+                }
+                imports.extend(urls.into_iter().filter(|url| !is_css_import(url)));
+                while chars.peek().is_some_and(|&(j, _)| j < end) {
+                    chars.next();
+                }
+            }
+            _ => {}
+        }
+    }
+    imports
+}
+
+/// Byte offset of the end of the at-rule starting `rule`: its `;`, or its line in the indented
+/// syntax.
+fn rule_end(rule: &str, indented: bool) -> Option<usize> {
+    let mut chars = rule.char_indices();
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '"' | '\'' => scan::skip_string(&mut chars, c),
+            ';' | '{' => return Some(i),
+            '\n' if indented => return Some(i),
+            _ => {}
+        }
+    }
+    None
+}
+
+/// The contents of the quoted strings in `rule`, in order.
+fn quoted(rule: &str) -> Vec<String> {
+    let mut strings = Vec::new();
+    let mut chars = rule.chars();
+    while let Some(c) = chars.next() {
+        if c == '"' || c == '\'' {
+            let mut string = String::new();
+            let mut escaped = false;
+            for next in chars.by_ref() {
+                match next {
+                    _ if escaped => {
+                        escaped = false;
+                        string.push(next);
+                    }
+                    '\\' => escaped = true,
+                    next if next == c => break,
+                    _ => string.push(next),
+                }
+            }
+            strings.push(string);
+        }
+    }
+    strings
+}
+
+/// Whether `url` is left to the browser, or is a built-in module, rather than loaded by grass.
+fn is_css_import(url: &str) -> bool {
+    url.starts_with("http://")
+        || url.starts_with("https://")
+        || url.starts_with("//")
+        || url.starts_with("sass:")
+        || url.ends_with(".css")
+}
+
+/// The file `url` loads when imported from `dir`, following Sass's partial and index rules.
+fn resolve(dir: &Path, url: &str) -> Option<PathBuf> {
+    let path = dir.join(url);
+    let name = path.file_name()?.to_str()?.to_owned();
+    let parent = path.parent()?;
+
+    let mut candidates = Vec::new();
+    if name.ends_with(".scss") || name.ends_with(".sass") {
+        candidates.push(parent.join(format!("_{}", name)));
+        candidates.push(path.clone());
+    } else {
+        for ext in ["scss", "sass"] {
+            candidates.push(parent.join(format!("_{}.{}", name, ext)));
+            candidates.push(parent.join(format!("{}.{}", name, ext)));
+        }
+        for ext in ["scss", "sass"] {
+            candidates.push(path.join(format!("_index.{}", ext)));
+            candidates.push(path.join(format!("index.{}", ext)));
+        }
+    }
+    candidates.into_iter().find(|candidate| candidate.is_file())
+}
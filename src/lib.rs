@@ -18,93 +18,1114 @@
 //! # let client = Client::tracked(rocket()).expect("valid rocket instance");
 //! # let response = client.get("/assets/style.css").dispatch();
 //! # assert_eq!(response.status(), Status::Ok);
+//! # assert_eq!(response.into_string().unwrap(), "a b {\n  color: a b;\n}\n");
+//! # let sheet = client.rocket().state::<SassSheet>().unwrap();
+//! # assert!(sheet.integrity().starts_with("sha384-"));
+//! # assert_eq!(
+//! #     sheet.link_tag("/assets/style.css?v=\"1\""),
+//! #     format!(
+//! #         r#"<link rel="stylesheet" href="/assets/style.css?v=&quot;1&quot;" integrity="{}" crossorigin="anonymous">"#,
+//! #         sheet.integrity()
+//! #     )
+//! # );
+//! # assert_eq!(sheet.configured_path().as_deref(), Some(std::path::Path::new("assets/style.scss")));
+//! # assert!(sheet.resolved_path().unwrap().is_absolute());
+//! # assert!(sheet.compile_duration() > std::time::Duration::default());
+//! # assert!(!sheet.is_empty());
+//! ```
+//!
+//! # Configuration
+//!
+//! The fairing reads the following keys from Rocket's configuration:
+//!
+//! | key                     | type               | default             | description                                                                |
+//! |-------------------------|--------------------|---------------------|----------------------------------------------------------------------------|
+//! | `sass_sheet_path`       | path               | `assets/style.scss` | Sass/SCSS file to compile                                                  |
+//! | `assets_max_age`        | integer            | `86400`             | `Cache-control` max age, in seconds                                        |
+//! | `sass_output_style`     | string             | see below           | `expanded` or `compressed`                                                 |
+//! | `sass_indent`           | integer or `"tab"` | `2`                 | spaces per level of `expanded` output, or `tab`                            |
+//! | `sass_follow_symlinks`  | bool               | `true`              | whether to resolve symlinks in the sheet path                              |
+//! | `sass_passthrough`      | bool               | `.css` files only   | serve the sheet as-is instead of compiling it                              |
+//! | `sass_strict`           | bool               | `false`             | fail ignite when the sheet compiles to nothing                             |
+//! | `sass_read_retries`     | integer            | `0`                 | times to retry opening the sheet file before failing                       |
+//! | `sass_read_retry_delay` | integer            | `250`               | milliseconds before the first retry, doubled after each one up to a minute |
+//! | `sass_compile_timeout`  | integer            | none                | milliseconds a compilation may take before failing                         |
+//! | `sass_url_rewrites`     | table              | none                | `url()` references to rewrite, see [`rewrite_urls`]                        |
+//! | `sass_maintenance_path` | path               | none                | sheet to serve while in maintenance                                        |
+//! | `sass_max_import_depth` | integer            | `64`                | how deeply imports may nest                                                |
+//! | `sass_banner`           | bool or string     | `false`             | comment to prepend, see below                                              |
+//! | `sass_dump_to_stderr`   | bool               | `false`             | print the compiled sheet on ignite, outside of `release`                   |
+//!
+//! Unless `sass_output_style` is set, sheets are compiled `expanded` in debug builds and
+//! `compressed` in release builds. `sass_indent` only applies to `expanded` output:
+//!
+//! ```rust
+//! # use rocket::{get, routes, local::blocking::Client};
+//! # use rocket_sass_fairing::SassSheet;
+//! # #[get("/assets/style.css")]
+//! # async fn style(sheet: &SassSheet) -> &SassSheet { sheet }
+//! let figment = rocket::Config::figment()
+//!     .merge(("sass_output_style", "expanded"))
+//!     .merge(("sass_indent", "tab"));
+//! let rocket = rocket::custom(figment).attach(SassSheet::fairing());
+//! # let client = Client::tracked(rocket.mount("/", routes![style])).unwrap();
+//! # let response = client.get("/assets/style.css").dispatch();
+//! # assert_eq!(response.into_string().unwrap(), "a b {\n\tcolor: a b;\n}\n");
+//! ```
+//!
+//! The sheet path is normalized on ignite, which resolves symlinks. Unset `sass_follow_symlinks`
+//! to keep using the configured path as-is, e.g. when deploys atomically swap the symlink it
+//! points through.
+//!
+//! With `sass_passthrough`, already-compiled CSS is served with the same caching as a compiled
+//! sheet. `sass_output_style` and `sass_indent` have no effect on it.
+//!
+//! ```rust
+//! # use rocket::{get, routes, local::blocking::Client};
+//! # use rocket_sass_fairing::SassSheet;
+//! # #[get("/assets/style.css")]
+//! # async fn style(sheet: &SassSheet) -> &SassSheet { sheet }
+//! let figment = rocket::Config::figment().merge(("sass_passthrough", true));
+//! let rocket = rocket::custom(figment).attach(SassSheet::fairing());
+//! # let client = Client::tracked(rocket.mount("/", routes![style])).unwrap();
+//! # let response = client.get("/assets/style.css").dispatch();
+//! # let source = std::fs::read_to_string("assets/style.scss").unwrap();
+//! # assert_eq!(response.into_string().unwrap(), source);
+//! ```
+//!
+//! A sheet compiling to nothing (e.g. an empty file or a misplaced `@use`) is almost always a
+//! mistake, so it's logged as a warning. Set `sass_strict` to make it fail ignite instead:
+//!
+//! ```rust
+//! # use rocket::local::blocking::Client;
+//! # use rocket_sass_fairing::SassSheet;
+//! let empty_sheet = || SassSheet::fairing().source_fn(|_| "// Nothing yet".into());
+//!
+//! let rocket = rocket::build().attach(empty_sheet());
+//! # let client = Client::tracked(rocket).expect("empty sheets only warn");
+//! # assert!(client.rocket().state::<SassSheet>().unwrap().is_empty());
+//!
+//! let figment = rocket::Config::figment().merge(("sass_strict", true));
+//! let rocket = rocket::custom(figment).attach(empty_sheet());
+//! # let error = Client::tracked(rocket).unwrap_err();
+//! # assert!(matches!(error.kind(), rocket::error::ErrorKind::FailedFairings(_)));
+//! ```
+//!
+//! `sass_compile_timeout`, a number of milliseconds, guards against sheets that take
+//! unreasonably long to compile, such as huge loops in generated SCSS. It's unset by default,
+//! letting compilation take as long as it needs:
+//!
+//! ```rust
+//! # use rocket::local::blocking::Client;
+//! # use rocket_sass_fairing::SassSheet;
+//! // Give up after 10ms.
+//! let figment = rocket::Config::figment().merge(("sass_compile_timeout", 10));
+//! let rocket = rocket::custom(figment).attach(SassSheet::fairing().source_fn(|_| {
+//!     "@for $i from 1 through 100000000 { a { b: $i; } }".into()
+//! }));
+//! # let error = Client::tracked(rocket).unwrap_err();
+//! # assert!(matches!(error.kind(), rocket::error::ErrorKind::FailedFairings(_)));
+//! ```
+//!
+//! The compiler can't be interrupted, so a compilation that times out keeps running on its own
+//! thread until it's done, and its result is thrown away. That includes the ones started by
+//! [`SassSheet::reload_from_figment`]: reloading a sheet that keeps timing out leaves a thread
+//! behind each time.
+//!
+//! `assets_max_age` is a number of seconds, so negative values fail ignite. Values above `2^31`
+//! (about 68 years), which caches aren't required to handle, are capped to it:
+//!
+//! ```rust
+//! # use rocket::{get, routes, local::blocking::Client};
+//! # use rocket_sass_fairing::SassSheet;
+//! # #[get("/assets/style.css")]
+//! # async fn style(sheet: &SassSheet) -> &SassSheet { sheet }
+//! let figment = rocket::Config::figment().merge(("assets_max_age", 100_u64 * 365 * 86400));
+//! let rocket = rocket::custom(figment).attach(SassSheet::fairing());
+//! # let client = Client::tracked(rocket.mount("/", routes![style])).unwrap();
+//! # let response = client.get("/assets/style.css").dispatch();
+//! # assert_eq!(response.headers().get_one("Cache-control"), Some("max-age=2147483648"));
+//! # let figment = rocket::Config::figment().merge(("assets_max_age", -1));
+//! # let error = Client::tracked(rocket::custom(figment).attach(SassSheet::fairing())).unwrap_err();
+//! # assert!(matches!(error.kind(), rocket::error::ErrorKind::FailedFairings(_)));
+//! ```
+//!
+//! `sass_maintenance_path` compiles a second sheet alongside the main one, served in its place
+//! while [`SassSheet::set_maintenance`] is on.
+//!
+//! `sass_max_import_depth` (64 by default) bounds how deeply a sheet's `@import` and `@use` rules
+//! can nest, failing ignite on circular imports rather than letting the compiler recurse without
+//! end:
+//!
+//! ```rust
+//! # use rocket::local::blocking::Client;
+//! # use rocket_sass_fairing::SassSheet;
+//! # let dir = std::env::temp_dir().join(format!("sass-imports-{}", std::process::id()));
+//! # std::fs::create_dir_all(&dir).unwrap();
+//! # std::fs::write(dir.join("_a.scss"), "@import 'b';").unwrap();
+//! # std::fs::write(dir.join("_b.scss"), "@import 'a';").unwrap();
+//! # std::fs::write(dir.join("style.scss"), "@import 'a';").unwrap();
+//! # let path = dir.join("style.scss");
+//! // `style.scss` imports `_a.scss`, which imports `_b.scss`, which imports `_a.scss` again.
+//! let figment = rocket::Config::figment()
+//!     .merge(("sass_sheet_path", path))
+//!     .merge(("sass_max_import_depth", 16));
+//! let rocket = rocket::custom(figment).attach(SassSheet::fairing());
+//! # let error = Client::tracked(rocket).unwrap_err();
+//! # assert!(matches!(error.kind(), rocket::error::ErrorKind::FailedFairings(_)));
+//! # std::fs::remove_dir_all(&dir).unwrap();
+//! ```
+//!
+//! `sass_banner` prepends a comment naming the sheet and its [version](SassSheet::version), off
+//! by default. Set it to `true` for the default text, or to a template using `{file}` and
+//! `{version}`:
+//!
+//! ```rust
+//! # use rocket::{get, routes, local::blocking::Client};
+//! # use rocket_sass_fairing::SassSheet;
+//! # #[get("/assets/style.css")]
+//! # async fn style(sheet: &SassSheet) -> &SassSheet { sheet }
+//! let figment = rocket::Config::figment()
+//!     .merge(("sass_output_style", "compressed"))
+//!     .merge(("sass_banner", "{file}, build {version}"));
+//! let rocket = rocket::custom(figment).attach(SassSheet::fairing());
+//! # let client = Client::tracked(rocket.mount("/", routes![style])).unwrap();
+//! # let sheet = client.rocket().state::<SassSheet>().unwrap();
+//! # let expected = format!("/*! style.scss, build {} */a b{{color:a b}}", sheet.version());
+//! # assert_eq!(client.get("/assets/style.css").dispatch().into_string().unwrap(), expected);
+//! # for (output_style, expected) in [
+//! #     ("compressed", "\u{FEFF}/*! generated sass */a{content:\"é\"}"),
+//! #     ("expanded", "@charset \"UTF-8\";\n/* generated sass */\na {\n  content: \"é\";\n}\n"),
+//! # ] {
+//! #     let figment = rocket::Config::figment()
+//! #         .merge(("sass_output_style", output_style))
+//! #         .merge(("sass_banner", "{file}"));
+//! #     let fairing = SassSheet::fairing().source_fn(|_| "a { content: 'é'; }".into());
+//! #     let rocket = rocket::custom(figment).attach(fairing).mount("/", routes![style]);
+//! #     let client = Client::tracked(rocket).unwrap();
+//! #     assert_eq!(client.get("/assets/style.css").dispatch().into_string().unwrap(), expected);
+//! # }
+//! ```
+//!
+//! To inspect the generated CSS in CI or container logs, set `sass_dump_to_stderr` to print the
+//! compiled sheet to stderr once, on ignite. It has no effect in the `release` profile.
+//!
+//! `sass_url_rewrites` maps references found in the compiled sheet's `url()`s to the ones to
+//! serve instead, e.g. the fingerprinted names of images:
+//!
+//! ```toml
+//! [default.sass_url_rewrites]
+//! "/img/logo.png" = "/img/logo.3f2a9c.png"
+//! ```
+//!
+//! Relative paths are resolved against the configuration file that set them, or against the
+//! working directory when they come from the environment.
+//!
+//! As with any other Rocket option, every key can be set in `Rocket.toml` and overridden by an
+//! environment variable of the same name, upper-cased and prefixed with `ROCKET_`:
+//!
+//! ```sh
+//! ROCKET_SASS_SHEET_PATH=static/main.scss ROCKET_ASSETS_MAX_AGE=3600 cargo run
+//! ```
+//!
+//! ```rust
+//! # use rocket::{get, routes, local::blocking::Client};
+//! # use rocket_sass_fairing::SassSheet;
+//! # #[get("/assets/style.css")]
+//! # async fn style(sheet: &SassSheet) -> &SassSheet { sheet }
+//! # fn rocket() -> rocket::Rocket<rocket::Build> {
+//! #     rocket::build().attach(SassSheet::fairing()).mount("/", routes![style])
+//! # }
+//! std::env::set_var("ROCKET_ASSETS_MAX_AGE", "3600");
+//! std::env::set_var("ROCKET_SASS_OUTPUT_STYLE", "compressed");
+//! # let client = Client::tracked(rocket()).expect("valid rocket instance");
+//! # let response = client.get("/assets/style.css").dispatch();
+//! # assert_eq!(response.headers().get_one("Cache-control"), Some("max-age=3600"));
 //! # assert_eq!(response.into_string().unwrap(), "a b{color:a b}");
+//! # drop(client);
+//!
+//! // Takes precedence over the `sass_sheet_path` set in `Rocket.toml`.
+//! std::env::set_var("ROCKET_SASS_SHEET_PATH", "assets/missing.scss");
+//! # std::env::set_var("ROCKET_SASS_READ_RETRIES", "2");
+//! # std::env::set_var("ROCKET_SASS_READ_RETRY_DELAY", "1");
+//! # let error = Client::tracked(rocket()).unwrap_err();
+//! # assert!(matches!(error.kind(), rocket::error::ErrorKind::FailedFairings(_)));
 //! ```
+//!
+//! # Limitations
+//!
+//! Sheets are compiled with grass 0.10, which doesn't implement `@forward` yet: any sheet using
+//! it, directly or through an import, fails to compile. grass panics on the rule, so besides the
+//! compile error the fairing logs, Rust's panic hook prints a `panicked at` line to stderr.
 use normpath::PathExt;
 use rocket::{
     error,
     fairing::{self, Fairing, Info, Kind},
+    figment::{self, value::magic::RelativePathBuf, Figment},
     http::ContentType,
     info, info_,
     outcome::IntoOutcome,
     request::{self, FromRequest, Request},
     response::{self, Responder, Response},
-    Build, Orbit, Rocket,
+    warn, Build, Ignite, Orbit, Rocket, Sentinel,
 };
-use std::path::PathBuf;
+use sha2::{Digest, Sha384};
+use std::{
+    fmt,
+    fs::{self, File},
+    io::{self, Cursor, Read},
+    ops::Deref,
+    path::{Path, PathBuf},
+    sync::{Arc, PoisonError, RwLock, RwLockReadGuard, RwLockWriteGuard},
+    time::{Duration, Instant},
+};
+
+pub use config::SassConfig;
+#[cfg(feature = "diff")]
+pub use diff::CssDiff;
+pub use grass;
+#[cfg(feature = "report")]
+pub use report::{SassSymbol, SheetReport};
+pub use server::SassSheetServer;
+pub use urls::rewrite_urls;
+
+mod config;
+#[cfg(feature = "diff")]
+mod diff;
+mod format;
+mod imports;
+#[cfg(feature = "report")]
+mod report;
+mod scan;
+mod server;
+mod urls;
 
 pub struct SassSheet {
-    content: String,
-    cache_max_age: i32,
-    path: PathBuf,
+    sheets: RwLock<Sheets>,
+    /// The fairing's [`SassSheetFairing::configure`], applied again on reload.
+    configure: Option<ConfigureFn>,
 }
 
-impl SassSheet {
-    pub fn fairing() -> impl Fairing {
-        SassSheetFairing
+/// The compiled sheets, swapped as a whole on reload.
+struct Sheets {
+    primary: Compiled,
+    maintenance: Option<Compiled>,
+    in_maintenance: bool,
+}
+
+impl Sheets {
+    fn served(&self) -> &Compiled {
+        match &self.maintenance {
+            Some(maintenance) if self.in_maintenance => maintenance,
+            _ => &self.primary,
+        }
     }
 }
 
-struct SassSheetFairing;
+impl From<Compiled> for Sheets {
+    fn from(primary: Compiled) -> Self {
+        Sheets {
+            primary,
+            maintenance: None,
+            in_maintenance: false,
+        }
+    }
+}
 
-#[rocket::async_trait]
-impl Fairing for SassSheetFairing {
-    fn info(&self) -> Info {
-        Info {
-            kind: Kind::Ignite | Kind::Liftoff,
-            name: "Sass Sheet",
+/// Read access to the sheet being served.
+struct Current<'a>(RwLockReadGuard<'a, Sheets>);
+
+impl Deref for Current<'_> {
+    type Target = Compiled;
+
+    fn deref(&self) -> &Compiled {
+        self.0.served()
+    }
+}
+
+/// Everything known about a compilation.
+struct Compiled {
+    content: Arc<str>,
+    cache_max_age: u64,
+    /// `Cache-control` value, formatted once rather than on every response.
+    cache_control: String,
+    configured_path: Option<PathBuf>,
+    path: Option<PathBuf>,
+    integrity: String,
+    version: String,
+    compile_duration: Duration,
+    /// Whether the CSS, banner aside, is only whitespace.
+    empty: bool,
+}
+
+impl Compiled {
+    fn new(content: String, cache_max_age: u64) -> Self {
+        let digest = Sha384::digest(content.as_bytes());
+        Compiled {
+            integrity: compute_integrity(&digest),
+            version: digest[..8]
+                .iter()
+                .map(|byte| format!("{:02x}", byte))
+                .collect(),
+            empty: content.trim().is_empty(),
+            content: content.into(),
+            cache_max_age,
+            cache_control: format!("max-age={}", cache_max_age),
+            configured_path: None,
+            path: None,
+            compile_duration: Duration::default(),
         }
     }
 
-    async fn on_ignite(&self, rocket: Rocket<Build>) -> fairing::Result {
-        use rocket::figment::value::magic::RelativePathBuf;
+    /// Prepends a comment rendered from the `sass_banner` `template`. The version stays the one
+    /// of the content without it, so the banner can name it.
+    fn with_banner(mut self, template: &str, output_style: grass::OutputStyle) -> Self {
+        let file = self
+            .path
+            .as_deref()
+            .and_then(Path::file_name)
+            .map_or_else(|| "generated sass".into(), |name| name.to_string_lossy());
+        let text = template
+            .replace("{file}", &file)
+            .replace("{version}", &self.version)
+            .replace("*/", "* /");
+        // The byte order mark or `@charset` grass starts non-ASCII sheets with has to stay first.
+        let start = if self.content.starts_with('\u{FEFF}') {
+            '\u{FEFF}'.len_utf8()
+        } else if self.content.starts_with("@charset") {
+            self.content.find(";\n").map_or(0, |end| end + ";\n".len())
+        } else {
+            0
+        };
+        let (charset, rest) = self.content.split_at(start);
+        let content = match output_style {
+            // A bang comment survives minification.
+            grass::OutputStyle::Compressed => format!("{}/*! {} */{}", charset, text, rest),
+            _ => format!("{}/* {} */\n{}", charset, text, rest),
+        };
+        self.integrity = compute_integrity(&Sha384::digest(content.as_bytes()));
+        self.content = content.into();
+        self
+    }
+}
 
-        let configured_path = rocket
-            .figment()
-            .extract_inner::<RelativePathBuf>("sass_sheet_path")
-            .map(|path| path.relative());
+impl SassSheet {
+    pub fn fairing() -> SassSheetFairing {
+        SassSheetFairing {
+            source: None,
+            configure: None,
+        }
+    }
 
-        let relative_path = match configured_path {
-            Ok(path) => path,
-            Err(e) if e.missing() => "assets/style.scss".into(),
-            Err(e) => {
-                rocket::config::pretty_print_error(e);
-                return Err(rocket);
+    /// A fairing managing a sheet with the given, already-compiled `css`, so route tests don't
+    /// need to touch the disk or the compiler.
+    /// ```rust
+    /// use rocket_sass_fairing::SassSheet;
+    ///
+    /// let rocket = rocket::build().attach(SassSheet::test_fairing("a{color:red}"));
+    /// # use rocket::{get, routes, local::blocking::Client};
+    /// # #[get("/assets/style.css")]
+    /// # async fn style(sheet: &SassSheet) -> &SassSheet { sheet }
+    /// # let client = Client::tracked(rocket.mount("/", routes![style])).unwrap();
+    /// # let response = client.get("/assets/style.css").dispatch();
+    /// # assert_eq!(response.headers().get_one("Cache-control"), Some("max-age=86400"));
+    /// # assert_eq!(response.into_string().unwrap(), "a{color:red}");
+    /// ```
+    #[cfg(feature = "testing")]
+    pub fn test_fairing(css: &str) -> impl Fairing {
+        let css = css.to_owned();
+        rocket::fairing::AdHoc::try_on_ignite("Sass Sheet (testing)", |rocket| async move {
+            match SassConfig::from_figment(rocket.figment()) {
+                Ok(config) => Ok(rocket.manage(SassSheet::wrap(
+                    Compiled::new(css, config.capped_max_age()).into(),
+                ))),
+                Err(e) => {
+                    log_error(e);
+                    Err(rocket)
+                }
             }
+        })
+    }
+
+    /// Compiles SCSS read from `reader` into a sheet that isn't backed by any file.
+    ///
+    /// Imports are resolved against the working directory, and held to the default
+    /// `sass_max_import_depth`. The sheet can then be handed to Rocket without the fairing:
+    /// ```rust
+    /// use rocket_sass_fairing::{default_options, SassSheet};
+    ///
+    /// let source = "a { b { color: &; } }".as_bytes();
+    /// let sheet = SassSheet::from_reader(source, &default_options()).expect("valid sass");
+    /// let rocket = rocket::build().manage(sheet);
+    /// # use rocket::{get, routes, local::blocking::Client};
+    /// # #[get("/assets/style.css")]
+    /// # async fn style(sheet: &SassSheet) -> &SassSheet { sheet }
+    /// # let client = Client::tracked(rocket.mount("/", routes![style])).unwrap();
+    /// # let response = client.get("/assets/style.css").dispatch();
+    /// # assert_eq!(response.into_string().unwrap(), "a b {\n  color: a b;\n}\n");
+    /// # let dir = std::env::temp_dir().join(format!("sass-reader-{}", std::process::id()));
+    /// # std::fs::create_dir_all(&dir).unwrap();
+    /// # std::fs::write(dir.join("_a.scss"), "@import 'b';").unwrap();
+    /// # std::fs::write(dir.join("_b.scss"), "@import 'a';").unwrap();
+    /// # let source = format!("@import '{}';", dir.join("a").display());
+    /// # let error = SassSheet::from_reader(source.as_bytes(), &default_options()).err().unwrap();
+    /// # assert!(matches!(error, rocket_sass_fairing::SassError::ImportTooDeep { .. }));
+    /// # std::fs::remove_dir_all(&dir).unwrap();
+    /// ```
+    pub fn from_reader<R: Read>(
+        mut reader: R,
+        options: &grass::Options,
+    ) -> Result<Self, SassError> {
+        let mut source = String::new();
+        reader.read_to_string(&mut source).map_err(SassError::Io)?;
+        imports::check_source(
+            &source,
+            Path::new(""),
+            false,
+            config::DEFAULT_MAX_IMPORT_DEPTH,
+        )?;
+        let started = Instant::now();
+        let content = compile_source(source, options)?;
+        let compiled = Compiled {
+            compile_duration: started.elapsed(),
+            ..Compiled::new(content, config::DEFAULT_MAX_AGE)
         };
+        Ok(SassSheet::wrap(compiled.into()))
+    }
 
-        let path = match relative_path.normalize() {
-            Ok(path) => path.into_path_buf(),
-            Err(e) => {
-                error!(
-                    "Invalid sass sheet file '{}': {}.",
-                    relative_path.display(),
-                    e
-                );
-                return Err(rocket);
+    fn wrap(sheets: Sheets) -> Self {
+        SassSheet {
+            sheets: RwLock::new(sheets),
+            configure: None,
+        }
+    }
+
+    fn current(&self) -> Current<'_> {
+        Current(self.sheets.read().unwrap_or_else(PoisonError::into_inner))
+    }
+
+    fn sheets_mut(&self) -> RwLockWriteGuard<'_, Sheets> {
+        self.sheets.write().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Rebuilds the sheet from `figment`, the same way the fairing does on ignite, and starts
+    /// serving the result in place of the current sheet.
+    ///
+    /// Everything the sheet holds can change this way: its path, content, integrity hash and
+    /// cache max age. [`SassSheetFairing::configure`] is applied to the new configuration too.
+    /// The routes serving the sheet, and Rocket's own settings, still need a restart.
+    ///
+    /// Only a sheet compiled from a file can be reloaded. One generated with
+    /// [`SassSheetFairing::source_fn`], or built by [`SassSheet::from_reader`] or
+    /// `SassSheet::test_fairing`, fails with [`SassError::NotReloadable`] rather than being
+    /// swapped for the configured `sass_sheet_path`.
+    ///
+    /// [`Rocket::figment`] is the configuration as it was on launch; use
+    /// [`rocket::Config::figment`] to pick up changes made since to `Rocket.toml` or the
+    /// environment. When the new configuration doesn't build, the current sheet keeps being
+    /// served. A reload that hits `sass_compile_timeout` leaves its compilation running in the
+    /// background until it's done.
+    /// ```rust
+    /// use rocket::post;
+    /// use rocket_sass_fairing::SassSheet;
+    ///
+    /// #[post("/admin/reload-styles")]
+    /// async fn reload(sheet: &SassSheet) -> Result<(), String> {
+    ///     sheet
+    ///         .reload_from_figment(&rocket::Config::figment())
+    ///         .await
+    ///         .map_err(|e| e.to_string())
+    /// }
+    /// # use rocket::{get, routes, local::blocking::Client};
+    /// # #[get("/assets/style.css")]
+    /// # async fn style(sheet: &SassSheet) -> &SassSheet { sheet }
+    /// # let rocket = rocket::build().attach(SassSheet::fairing()).mount("/", routes![style, reload]);
+    /// # let client = Client::tracked(rocket).unwrap();
+    /// # std::env::set_var("ROCKET_ASSETS_MAX_AGE", "60");
+    /// # assert_eq!(client.post("/admin/reload-styles").dispatch().status(), rocket::http::Status::Ok);
+    /// # let response = client.get("/assets/style.css").dispatch();
+    /// # assert_eq!(response.headers().get_one("Cache-control"), Some("max-age=60"));
+    /// # let generated = SassSheet::from_reader("a { b: c }".as_bytes(), &rocket_sass_fairing::default_options()).unwrap();
+    /// # let runtime = rocket::tokio::runtime::Runtime::new().unwrap();
+    /// # let error = runtime.block_on(generated.reload_from_figment(&rocket::Config::figment()));
+    /// # assert!(matches!(error, Err(rocket_sass_fairing::SassError::NotReloadable)));
+    /// ```
+    pub async fn reload_from_figment(&self, figment: &Figment) -> Result<(), SassError> {
+        if self.current().0.primary.path.is_none() {
+            return Err(SassError::NotReloadable);
+        }
+        let mut config = SassConfig::from_figment(figment)?;
+        if let Some(configure) = &self.configure {
+            config = configure(config);
+        }
+        let mut sheets = load(&config, None).await?;
+        let mut current = self.sheets_mut();
+        sheets.in_maintenance = current.in_maintenance && sheets.maintenance.is_some();
+        *current = sheets;
+        Ok(())
+    }
+
+    /// Starts or stops serving the `sass_maintenance_path` sheet in place of the main one, e.g.
+    /// from an admin route. Returns whether the maintenance sheet is now served, which it can't
+    /// be when none is configured.
+    ///
+    /// While it's served, the sheet's accessors describe the maintenance sheet. It stays served
+    /// across [reloads](SassSheet::reload_from_figment) as long as one is still configured.
+    ///
+    /// The maintenance sheet is sent with `Cache-control: no-cache`, so browsers revalidate it
+    /// rather than keep it once maintenance is over. Browsers that cached the main sheet keep
+    /// using it until its `assets_max_age` runs out, so set a short one if the maintenance sheet
+    /// must show up right away.
+    /// ```rust
+    /// use rocket_sass_fairing::SassSheet;
+    ///
+    /// let figment = rocket::Config::figment()
+    ///     .merge(("sass_maintenance_path", "assets/maintenance.scss"));
+    /// let rocket = rocket::custom(figment)
+    ///     .attach(SassSheet::fairing())
+    ///     .mount("/assets", SassSheet::mountable("style.css"));
+    /// # use rocket::local::blocking::Client;
+    /// # let client = Client::tracked(rocket).unwrap();
+    /// # let sheet = client.rocket().state::<SassSheet>().unwrap();
+    /// # let normal = client.get("/assets/style.css").dispatch().into_string().unwrap();
+    /// # assert!(!sheet.in_maintenance());
+    /// # assert!(sheet.set_maintenance(true));
+    /// # assert!(sheet.in_maintenance());
+    /// # let response = client.get("/assets/style.css").dispatch();
+    /// # assert_eq!(response.headers().get_one("Cache-control"), Some("no-cache"));
+    /// # assert!(response.into_string().unwrap().contains("maintenance"));
+    /// # assert!(!sheet.set_maintenance(false));
+    /// # let response = client.get("/assets/style.css").dispatch();
+    /// # assert_eq!(response.headers().get_one("Cache-control"), Some("max-age=86400"));
+    /// # assert_eq!(response.into_string().unwrap(), normal);
+    /// ```
+    pub fn set_maintenance(&self, enabled: bool) -> bool {
+        let mut sheets = self.sheets_mut();
+        sheets.in_maintenance = enabled && sheets.maintenance.is_some();
+        sheets.in_maintenance
+    }
+
+    /// Whether the maintenance sheet is being served in place of the main one.
+    pub fn in_maintenance(&self) -> bool {
+        self.current().0.in_maintenance
+    }
+
+    /// Whether the served sheet compiled to nothing but whitespace, which ignite warns about. A
+    /// banner doesn't count as content.
+    pub fn is_empty(&self) -> bool {
+        self.current().empty
+    }
+
+    /// How long compiling the sheet took. Zero for sheets that weren't compiled, like ones served
+    /// as-is.
+    pub fn compile_duration(&self) -> Duration {
+        self.current().compile_duration
+    }
+
+    /// The sheet path as written in the configuration. `None` if the sheet has no file.
+    pub fn configured_path(&self) -> Option<PathBuf> {
+        self.current().configured_path.clone()
+    }
+
+    /// The path the sheet was compiled from, normalized unless `sass_follow_symlinks` is unset.
+    /// `None` if the sheet has no file.
+    pub fn resolved_path(&self) -> Option<PathBuf> {
+        self.current().path.clone()
+    }
+
+    /// Subresource integrity hash of the compiled sheet (`sha384-...`).
+    pub fn integrity(&self) -> String {
+        self.current().integrity.clone()
+    }
+
+    /// A short identifier of the compiled content, changing whenever the sheet does. Also sent
+    /// as the `X-Asset-Version` header, so clients can poll it to notice a new sheet.
+    /// ```rust
+    /// use rocket_sass_fairing::{default_options, SassSheet};
+    ///
+    /// let compile = |scss: &str| SassSheet::from_reader(scss.as_bytes(), &default_options());
+    /// let sheet = compile("a { color: red }").unwrap();
+    ///
+    /// assert_eq!(sheet.version().len(), 16);
+    /// assert_eq!(sheet.version(), compile("a { color: red }").unwrap().version());
+    /// assert_ne!(sheet.version(), compile("a { color: blue }").unwrap().version());
+    /// # use rocket::{get, routes, local::blocking::Client};
+    /// # #[get("/assets/style.css")]
+    /// # async fn style(sheet: &SassSheet) -> &SassSheet { sheet }
+    /// # let version = sheet.version();
+    /// # let client = Client::tracked(rocket::build().manage(sheet).mount("/", routes![style])).unwrap();
+    /// # let response = client.get("/assets/style.css").dispatch();
+    /// # assert_eq!(response.headers().get_one("X-Asset-Version"), Some(&*version));
+    /// ```
+    pub fn version(&self) -> String {
+        self.current().version.clone()
+    }
+
+    /// The compiled sheet as a base64 `data:text/css` URI, for inlining very small sheets.
+    pub fn data_uri(&self) -> String {
+        self.data_uri_with(DataUriEncoding::default())
+    }
+
+    /// The compiled sheet as a `data:text/css` URI, with its content encoded as `encoding`.
+    /// ```rust
+    /// use rocket_sass_fairing::{default_options, DataUriEncoding, SassSheet};
+    ///
+    /// let options = default_options().style(rocket_sass_fairing::grass::OutputStyle::Compressed);
+    /// let sheet = SassSheet::from_reader("a { b: c }".as_bytes(), &options).unwrap();
+    ///
+    /// assert_eq!(sheet.data_uri(), "data:text/css;base64,YXtiOmN9");
+    /// assert_eq!(
+    ///     sheet.data_uri_with(DataUriEncoding::Percent),
+    ///     "data:text/css;charset=utf-8,a%7Bb:c%7D"
+    /// );
+    /// # let sheet = SassSheet::from_reader("a { b: '#é' 50% }".as_bytes(), &options).unwrap();
+    /// # assert_eq!(
+    /// #     sheet.data_uri_with(DataUriEncoding::Percent),
+    /// #     "data:text/css;charset=utf-8,%EF%BB%BFa%7Bb:%22%23%C3%A9%22%2050%25%7D"
+    /// # );
+    /// # let sheet = SassSheet::from_reader("a { b: url(c.png); d: \"it's\" }".as_bytes(), &options);
+    /// # assert_eq!(
+    /// #     sheet.unwrap().data_uri_with(DataUriEncoding::Percent),
+    /// #     "data:text/css;charset=utf-8,a%7Bb:url%28c.png%29;d:%22it%27s%22%7D"
+    /// # );
+    /// ```
+    pub fn data_uri_with(&self, encoding: DataUriEncoding) -> String {
+        let content = &self.current().content;
+        match encoding {
+            DataUriEncoding::Base64 => {
+                format!(
+                    "data:text/css;base64,{}",
+                    base64::encode(content.as_bytes())
+                )
             }
-        };
+            DataUriEncoding::Percent => {
+                format!("data:text/css;charset=utf-8,{}", percent_encode(content))
+            }
+        }
+    }
 
-        info!("Compiling sass file '{}'...", relative_path.display());
-        let options = grass::Options::default().style(grass::OutputStyle::Compressed);
-        let compiled_css = match grass::from_path(&path.to_string_lossy(), &options) {
-            Ok(css) => css,
-            Err(e) => {
-                error!("Couldn't compile sass: {}", e);
-                return Err(rocket);
+    /// A stylesheet `<link>` tag for `href`, carrying the sheet's integrity hash.
+    pub fn link_tag(&self, href: &str) -> String {
+        format!(
+            r#"<link rel="stylesheet" href="{}" integrity="{}" crossorigin="anonymous">"#,
+            escape_attribute(href),
+            self.current().integrity
+        )
+    }
+
+    /// Everything templates usually need about the sheet served at `href`, in one struct.
+    /// ```rust
+    /// use rocket_sass_fairing::{default_options, SassSheet};
+    ///
+    /// let sheet = SassSheet::from_reader("a { b: c }".as_bytes(), &default_options()).unwrap();
+    /// let info = sheet.asset_info("/assets/style.css");
+    ///
+    /// assert_eq!(info.url, "/assets/style.css");
+    /// assert_eq!(info.integrity, sheet.integrity());
+    /// assert_eq!(info.size, "a {\n  b: c;\n}\n".len());
+    /// assert_eq!(info.content_type, "text/css; charset=utf-8");
+    /// ```
+    pub fn asset_info(&self, href: &str) -> AssetInfo {
+        let current = self.current();
+        AssetInfo {
+            url: href.to_owned(),
+            version: current.version.clone(),
+            integrity: current.integrity.clone(),
+            size: current.content.len(),
+            content_type: ContentType::CSS.to_string(),
+        }
+    }
+
+    /// A `Link` header value hinting browsers to preload the sheet served at `href`, for the HTML
+    /// responses using it.
+    /// ```rust
+    /// use rocket::http::Header;
+    /// use rocket_sass_fairing::SassSheet;
+    ///
+    /// let link = SassSheet::preload_link("/assets/style.css");
+    /// assert_eq!(link, "</assets/style.css>; rel=preload; as=style");
+    /// let header = Header::new("Link", link);
+    /// ```
+    pub fn preload_link(href: &str) -> String {
+        format!("<{}>; rel=preload; as=style", href.replace('>', "%3E"))
+    }
+}
+
+/// The sheet's template-relevant strings, from [`SassSheet::asset_info`]. Serializable with the
+/// `serde` feature.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(rocket::serde::Serialize),
+    serde(crate = "rocket::serde")
+)]
+pub struct AssetInfo {
+    /// The public URL the sheet is served at.
+    pub url: String,
+    /// See [`SassSheet::version`].
+    pub version: String,
+    /// See [`SassSheet::integrity`].
+    pub integrity: String,
+    /// Size of the compiled sheet, in bytes.
+    pub size: usize,
+    /// The `Content-Type` the sheet is served with.
+    pub content_type: String,
+}
+
+/// How [`SassSheet::data_uri_with`] encodes the sheet.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DataUriEncoding {
+    /// `data:text/css;base64,...`, compact for any content.
+    #[default]
+    Base64,
+    /// `data:text/css;charset=utf-8,...` with the characters URLs can't carry as-is
+    /// percent-encoded, such as spaces, braces and non-ASCII text, along with quotes and
+    /// parentheses so the URI can go in a CSS `url()`. Punctuation like `:` and `;` is kept, so
+    /// the sheet stays mostly readable.
+    Percent,
+}
+
+/// Error raised while compiling a sheet.
+///
+/// Compile failures carry where grass located the error, 1-based, for editor integrations. The
+/// `file` can be one the sheet imports, rather than the sheet itself:
+/// ```rust
+/// use rocket_sass_fairing::{default_options, SassError, SassSheet};
+///
+/// let source = "a {\n  color: $undefined;\n}";
+/// match SassSheet::from_reader(source.as_bytes(), &default_options()) {
+///     Err(SassError::CompileFailed { file, line, column, .. }) => {
+///         assert_eq!((file, line, column), (None, Some(2), Some(10)));
+///     }
+///     _ => panic!("should fail to compile"),
+/// }
+/// # use rocket_sass_fairing::check_sheet;
+/// # let dir = std::env::temp_dir().join(format!("sass-error-{}", std::process::id()));
+/// # std::fs::create_dir_all(&dir).unwrap();
+/// # std::fs::write(dir.join("_part.scss"), "x {\n  y: $nope;\n}\n").unwrap();
+/// # std::fs::write(dir.join("style.scss"), "@import 'part';\na { b: c; }\n").unwrap();
+/// # match check_sheet(dir.join("style.scss"), &default_options()) {
+/// #     Err(SassError::CompileFailed { path, file, line, column, .. }) => {
+/// #         assert_eq!(path, Some(dir.join("style.scss")));
+/// #         assert_eq!(file, Some(dir.join("_part.scss")));
+/// #         assert_eq!((line, column), (Some(2), Some(6)));
+/// #     }
+/// #     _ => panic!("should fail to compile"),
+/// # }
+/// # std::fs::remove_dir_all(&dir).unwrap();
+/// ```
+#[derive(Debug)]
+pub enum SassError {
+    /// grass rejected the sheet; `message` holds its diagnostic, location included.
+    ///
+    /// `path` is the sheet being compiled, and `file` the one the error is in, which is `path`
+    /// or a file it imports. Either is `None` for sources that aren't read from a file.
+    CompileFailed {
+        path: Option<PathBuf>,
+        file: Option<PathBuf>,
+        line: Option<usize>,
+        column: Option<usize>,
+        message: String,
+    },
+    /// The sheet's source couldn't be read.
+    Io(io::Error),
+    /// Compilation took longer than the configured `sass_compile_timeout`.
+    TimedOut {
+        path: Option<PathBuf>,
+        timeout: Duration,
+    },
+    /// The configuration is invalid.
+    Config(Box<figment::Error>),
+    /// The configured sheet file couldn't be found or opened.
+    InvalidPath { path: PathBuf, error: io::Error },
+    /// No `sass_sheet_path` is configured, and there's no sheet at the default one.
+    DefaultPathMissing,
+    /// The sheet compiled to nothing, which `sass_strict` rejects.
+    Empty,
+    /// Imports nest deeper than `sass_max_import_depth`; `path` is the first file past it.
+    ImportTooDeep { path: PathBuf, max_depth: usize },
+    /// [`SassSheet::reload_from_figment`] was called on a sheet that wasn't compiled from a file.
+    NotReloadable,
+}
+
+impl SassError {
+    fn compile_failed(path: Option<PathBuf>, error: &grass::Error) -> Self {
+        let message = error.to_string().trim_end().to_owned();
+        // grass only exposes the location as the diagnostic's last line, `./file:line:column`,
+        // where the file of a source that wasn't read from one is `stdin`.
+        let mut location = message.lines().last().unwrap_or_default().rsplitn(3, ':');
+        let column = location.next().and_then(|column| column.parse().ok());
+        let line = location.next().and_then(|line| line.parse().ok());
+        let file = location.next().and_then(|file| file.strip_prefix("./"));
+        let (file, line, column) = match (file, line, column) {
+            (Some(file), Some(line), Some(column)) => {
+                let file = Some(PathBuf::from(file)).filter(|_| file != "stdin");
+                (file, Some(line), Some(column))
             }
+            _ => (None, None, None),
         };
+        SassError::CompileFailed {
+            path,
+            file,
+            line,
+            column,
+            message,
+        }
+    }
+}
 
-        let cache_max_age = rocket
-            .figment()
-            .extract_inner::<i32>("assets_max_age")
-            .unwrap_or(86400);
+impl fmt::Display for SassError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SassError::CompileFailed { message, .. } => f.write_str(message),
+            SassError::Io(e) => write!(f, "Couldn't read sass sheet: {}", e),
+            SassError::TimedOut {
+                path: Some(path),
+                timeout,
+            } => write!(
+                f,
+                "Compiling '{}' timed out after {:?}",
+                path.display(),
+                timeout
+            ),
+            SassError::TimedOut {
+                path: None,
+                timeout,
+            } => write!(f, "Compilation timed out after {:?}", timeout),
+            SassError::Config(e) => e.fmt(f),
+            SassError::InvalidPath { path, error } => {
+                write!(
+                    f,
+                    "Invalid sass sheet file '{}': {}.",
+                    path.display(),
+                    error
+                )
+            }
+            SassError::DefaultPathMissing => write!(
+                f,
+                "No sass sheet found at the default path '{}'. Create it, or point \
+                 `sass_sheet_path` (`ROCKET_SASS_SHEET_PATH`) at your sheet.",
+                config::DEFAULT_SHEET_PATH
+            ),
+            SassError::ImportTooDeep { path, max_depth } => write!(
+                f,
+                "Imports nest more than {} files deep at '{}', they may be circular.",
+                max_depth,
+                path.display()
+            ),
+            SassError::Empty => f.write_str("Sass sheet compiled to an empty stylesheet."),
+            SassError::NotReloadable => {
+                f.write_str("Sass sheet wasn't compiled from a file, so it can't be reloaded.")
+            }
+        }
+    }
+}
 
-        Ok(rocket.manage(SassSheet {
-            content: compiled_css,
-            cache_max_age,
-            path,
-        }))
+impl std::error::Error for SassError {}
+
+/// The grass options the fairing compiles with when no output style is configured: expanded
+/// output in debug builds, compressed output in release builds.
+pub fn default_options() -> grass::Options<'static> {
+    grass::Options::default().style(default_output_style())
+}
+
+fn default_output_style() -> grass::OutputStyle {
+    if cfg!(debug_assertions) {
+        grass::OutputStyle::Expanded
+    } else {
+        grass::OutputStyle::Compressed
+    }
+}
+
+/// Compiles the sheet at `path` and throws the output away, failing if it doesn't compile.
+///
+/// Meant for validating styles ahead of launch, e.g. from a `build.rs` or a CI test. Imports are
+/// held to the default `sass_max_import_depth`, as on ignite:
+/// ```rust
+/// use rocket_sass_fairing::{check_sheet, default_options};
+///
+/// if let Err(e) = check_sheet("assets/style.scss", &default_options()) {
+///     panic!("{}", e);
+/// }
+/// # assert!(check_sheet("assets/missing.scss", &default_options()).is_err());
+/// # let dir = std::env::temp_dir().join(format!("sass-check-{}", std::process::id()));
+/// # std::fs::create_dir_all(&dir).unwrap();
+/// # std::fs::write(dir.join("_a.scss"), "@import 'b';").unwrap();
+/// # std::fs::write(dir.join("_b.scss"), "@import 'a';").unwrap();
+/// # std::fs::write(dir.join("style.scss"), "@import 'a';").unwrap();
+/// # let error = check_sheet(dir.join("style.scss"), &default_options()).unwrap_err();
+/// # assert!(matches!(error, rocket_sass_fairing::SassError::ImportTooDeep { .. }));
+/// # std::fs::remove_dir_all(&dir).unwrap();
+/// ```
+pub fn check_sheet<P: AsRef<Path>>(path: P, options: &grass::Options) -> Result<(), SassError> {
+    let path = path.as_ref();
+    imports::check_import_depth(path, config::DEFAULT_MAX_IMPORT_DEPTH)?;
+    compile(path, options).map(drop)
+}
+
+fn compile(path: &Path, options: &grass::Options) -> Result<String, SassError> {
+    grass::from_path(&path.to_string_lossy(), options)
+        .map_err(|e| SassError::compile_failed(Some(path.to_path_buf()), &e))
+}
+
+fn compile_source(source: String, options: &grass::Options) -> Result<String, SassError> {
+    grass::from_string(source, options).map_err(|e| SassError::compile_failed(None, &e))
+}
+
+fn compute_integrity(digest: &[u8]) -> String {
+    format!("sha384-{}", base64::encode(digest))
+}
+
+/// Percent-encodes the bytes of `value` that aren't allowed in a URL, along with the `%` and `#`
+/// that would otherwise be taken for an escape or a fragment, and the `'`, `(` and `)` that
+/// would end a CSS `url()` the URI is embedded in.
+fn percent_encode(value: &str) -> String {
+    let mut encoded = String::with_capacity(value.len());
+    for byte in value.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' => encoded.push(byte as char),
+            b'-' | b'.' | b'_' | b'~' | b'!' | b'$' | b'&' | b'*' | b'+' | b',' | b';' | b'='
+            | b':' | b'@' | b'/' | b'?' => encoded.push(byte as char),
+            byte => encoded.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    encoded
+}
+
+fn escape_attribute(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// The fairing compiling and managing a [`SassSheet`], built by [`SassSheet::fairing`].
+pub struct SassSheetFairing {
+    source: Option<SourceFn>,
+    configure: Option<ConfigureFn>,
+}
+
+type SourceFn = Box<dyn Fn(&Rocket<Build>) -> String + Send + Sync>;
+type ConfigureFn = Arc<dyn Fn(SassConfig) -> SassConfig + Send + Sync>;
+
+impl SassSheetFairing {
+    /// Compiles the SCSS returned by `source` instead of reading `sass_sheet_path`.
+    ///
+    /// `source` runs on ignite with the rocket being built, so it can derive the sheet from
+    /// state managed by fairings attached before this one:
+    /// ```rust
+    /// use rocket_sass_fairing::SassSheet;
+    ///
+    /// struct Theme {
+    ///     accent: &'static str,
+    /// }
+    ///
+    /// let rocket = rocket::build()
+    ///     .manage(Theme { accent: "rebeccapurple" })
+    ///     .attach(SassSheet::fairing().source_fn(|rocket| {
+    ///         let theme = rocket.state::<Theme>().expect("theme is managed");
+    ///         format!("$accent: {}; a {{ color: $accent; }}", theme.accent)
+    ///     }));
+    /// # use rocket::{get, routes, local::blocking::Client};
+    /// # #[get("/assets/style.css")]
+    /// # async fn style(sheet: &SassSheet) -> &SassSheet { sheet }
+    /// # let client = Client::tracked(rocket.mount("/", routes![style])).unwrap();
+    /// # let response = client.get("/assets/style.css").dispatch();
+    /// # assert_eq!(response.into_string().unwrap(), "a {\n  color: rebeccapurple;\n}\n");
+    /// ```
+    pub fn source_fn<F>(mut self, source: F) -> Self
+    where
+        F: Fn(&Rocket<Build>) -> String + Send + Sync + 'static,
+    {
+        self.source = Some(Box::new(source));
+        self
+    }
+
+    /// Transforms the configuration extracted on ignite, and on every
+    /// [`SassSheet::reload_from_figment`], before the sheet is compiled with it, for settings
+    /// that config files can't express.
+    /// ```rust
+    /// use rocket_sass_fairing::SassSheet;
+    /// use std::path::PathBuf;
+    ///
+    /// let fairing = SassSheet::fairing().configure(|mut config| {
+    ///     let theme = std::env::var("THEME").unwrap_or_else(|_| "style".into());
+    ///     config.sheet_path = Some(PathBuf::from(format!("assets/{}.scss", theme)).into());
+    ///     config
+    /// });
+    /// # use rocket::{get, routes, local::blocking::Client};
+    /// # #[get("/assets/style.css")]
+    /// # async fn style(sheet: &SassSheet) -> &SassSheet { sheet }
+    /// # let client = Client::tracked(rocket::build().attach(fairing).mount("/", routes![style])).unwrap();
+    /// # let response = client.get("/assets/style.css").dispatch();
+    /// # assert_eq!(response.into_string().unwrap(), "a b {\n  color: a b;\n}\n");
+    /// # let figment = rocket::Config::figment().merge(("sass_sheet_path", "assets/missing.scss"));
+    /// # let sheet = client.rocket().state::<SassSheet>().unwrap();
+    /// # let runtime = rocket::tokio::runtime::Runtime::new().unwrap();
+    /// # runtime.block_on(sheet.reload_from_figment(&figment)).unwrap();
+    /// # let response = client.get("/assets/style.css").dispatch();
+    /// # assert_eq!(response.into_string().unwrap(), "a b {\n  color: a b;\n}\n");
+    /// ```
+    pub fn configure<F>(mut self, configure: F) -> Self
+    where
+        F: Fn(SassConfig) -> SassConfig + Send + Sync + 'static,
+    {
+        self.configure = Some(Arc::new(configure));
+        self
+    }
+
+    /// Loads the sheet on ignite, printing it to stderr when `sass_dump_to_stderr` is set outside
+    /// of the release profile.
+    async fn ignite(&self, rocket: &Rocket<Build>) -> Result<Sheets, SassError> {
+        let mut config = SassConfig::from_figment(rocket.figment())?;
+        if let Some(configure) = &self.configure {
+            config = configure(config);
+        }
+        let source = self.source.as_ref().map(|source| source(rocket));
+
+        let sheets = load(&config, source).await?;
+        if config.dump_to_stderr {
+            if rocket.figment().profile() == rocket::Config::RELEASE_PROFILE {
+                warn!("Ignoring `sass_dump_to_stderr` in the release profile.");
+            } else {
+                eprintln!(
+                    "/* ---- compiled sass sheet ---- */\n{}\n/* ---- end of sass sheet ---- */",
+                    sheets.primary.content.trim_end()
+                );
+            }
+        }
+        Ok(sheets)
+    }
+}
+
+#[rocket::async_trait]
+impl Fairing for SassSheetFairing {
+    fn info(&self) -> Info {
+        Info {
+            kind: Kind::Ignite | Kind::Liftoff,
+            name: "Sass Sheet",
+        }
+    }
+
+    async fn on_ignite(&self, rocket: Rocket<Build>) -> fairing::Result {
+        match self.ignite(&rocket).await {
+            Ok(sheets) => Ok(rocket.manage(SassSheet {
+                configure: self.configure.clone(),
+                ..SassSheet::wrap(sheets)
+            })),
+            Err(e) => {
+                log_error(e);
+                Err(rocket)
+            }
+        }
     }
 
     async fn on_liftoff(&self, rocket: &Rocket<Orbit>) {
@@ -112,11 +1133,262 @@ impl Fairing for SassSheetFairing {
 
         let state = rocket
             .state::<SassSheet>()
-            .expect("SassSheet registered in on_ignite");
+            .expect("SassSheet registered in on_ignite")
+            .current();
 
         info!("{}{}:", Paint::emoji("📐 "), Paint::magenta("Assets"));
-        info_!("sheet path: {}", Paint::white(Source::from(&*state.path)));
+        if let Some(path) = &state.configured_path {
+            info_!("configured path: {}", Paint::white(path.display()));
+        }
+        if let Some(path) = &state.path {
+            info_!("sheet path: {}", Paint::white(Source::from(&**path)));
+        }
+        if let Some(path) = state.0.maintenance.as_ref().and_then(|m| m.path.as_ref()) {
+            info_!(
+                "maintenance sheet path: {}",
+                Paint::white(Source::from(&**path))
+            );
+        }
         info_!("cache max age: {}", Paint::white(state.cache_max_age));
+        info_!(
+            "compile time: {}",
+            Paint::white(format!("{:?}", state.compile_duration))
+        );
+    }
+}
+
+/// Compiles the sheets configured in `config`, the main one from the already generated `source`
+/// if given.
+async fn load(config: &SassConfig, source: Option<String>) -> Result<Sheets, SassError> {
+    let primary = load_sheet(config, source, config.sheet_path.as_ref()).await?;
+    let maintenance = match &config.maintenance_path {
+        Some(path) => Some(load_sheet(config, None, Some(path)).await?),
+        None => None,
+    };
+    Ok(Sheets {
+        primary,
+        maintenance,
+        in_maintenance: false,
+    })
+}
+
+/// Compiles `source`, or else the sheet at `sheet_path`, defaulting to `assets/style.scss`.
+async fn load_sheet(
+    config: &SassConfig,
+    source: Option<String>,
+    sheet_path: Option<&RelativePathBuf>,
+) -> Result<Compiled, SassError> {
+    let output_style = config.output_style;
+    let options = move || grass::Options::default().style(output_style);
+    let timeout = config.compile_timeout;
+    let max_import_depth = config.max_import_depth;
+
+    let reindent = |css: String| match output_style {
+        grass::OutputStyle::Expanded if config.indent != "  " => {
+            format::reindent(&css, &config.indent)
+        }
+        _ => css,
+    };
+
+    let mut compile_duration = Duration::default();
+    let (mut compiled_css, configured_path, path) = match source {
+        Some(source) => {
+            info!("Compiling generated sass...");
+            let started = Instant::now();
+            let compiled_css = compile_blocking(None, timeout, move || {
+                // Generated sheets import relative to the working directory.
+                imports::check_source(&source, Path::new(""), false, max_import_depth)?;
+                compile_source(source, &options())
+            })
+            .await
+            .map(reindent)?;
+            compile_duration = started.elapsed();
+            (compiled_css, None, None)
+        }
+        None => {
+            let configured_path = sheet_path
+                .cloned()
+                .unwrap_or_else(|| RelativePathBuf::from(Path::new(config::DEFAULT_SHEET_PATH)));
+            let relative_path = configured_path.relative();
+            let resolved = resolve_sheet_path(&relative_path, config).await;
+            let path = resolved.map_err(|error| match error.kind() {
+                io::ErrorKind::NotFound if sheet_path.is_none() => SassError::DefaultPathMissing,
+                _ => SassError::InvalidPath {
+                    path: relative_path.clone(),
+                    error,
+                },
+            })?;
+
+            let is_css = path.extension().is_some_and(|ext| ext == "css");
+            let compiled_css = if config.passthrough.unwrap_or(is_css) {
+                info!("Serving css file '{}' as-is...", relative_path.display());
+                fs::read_to_string(&path).map_err(SassError::Io)?
+            } else {
+                info!("Compiling sass file '{}'...", relative_path.display());
+                let sheet_path = path.clone();
+                let started = Instant::now();
+                let compiled_css = compile_blocking(Some(&path), timeout, move || {
+                    imports::check_import_depth(&sheet_path, max_import_depth)?;
+                    compile(&sheet_path, &options())
+                })
+                .await
+                .map(reindent)?;
+                compile_duration = started.elapsed();
+                compiled_css
+            };
+            let configured_path = configured_path.original().to_path_buf();
+            (compiled_css, Some(configured_path), Some(path))
+        }
+    };
+
+    if !config.url_rewrites.is_empty() {
+        compiled_css = rewrite_urls(&compiled_css, &config.url_rewrites);
+    }
+
+    let compiled = Compiled {
+        configured_path,
+        path,
+        compile_duration,
+        ..Compiled::new(compiled_css, config.capped_max_age())
+    };
+    if compiled.empty {
+        if config.strict {
+            return Err(SassError::Empty);
+        }
+        warn!("Sass sheet compiled to an empty stylesheet, pages using it will be unstyled.");
+    }
+    Ok(match &config.banner {
+        Some(template) => compiled.with_banner(template, output_style),
+        None => compiled,
+    })
+}
+
+fn log_error(error: SassError) {
+    match error {
+        SassError::Config(e) => rocket::config::pretty_print_error(*e),
+        e @ SassError::CompileFailed { .. } | e @ SassError::TimedOut { .. } => {
+            error!("Couldn't compile sass: {}", e)
+        }
+        e => error!("{}", e),
+    }
+}
+
+/// Runs `compile` on its own thread so a pathological sheet can't stall ignite for longer than
+/// `timeout`, when one is set.
+///
+/// A compilation that times out can't be interrupted and keeps running in the background. A
+/// dedicated thread is used rather than the blocking pool so that it doesn't also hold up the
+/// runtime's shutdown. A panicking compiler is reported as a failed compilation, so it fails
+/// ignite instead of taking the process down. The panic hook is left alone, as it's global to
+/// the process, so the panic is still printed to stderr:
+/// ```rust
+/// # use rocket::local::blocking::Client;
+/// # use rocket_sass_fairing::SassSheet;
+/// // grass 0.10 panics on every `@forward`, it's not implemented yet.
+/// let rocket = rocket::build().attach(SassSheet::fairing().source_fn(|_| "@forward".into()));
+/// let error = Client::tracked(rocket).unwrap_err();
+/// assert!(matches!(error.kind(), rocket::error::ErrorKind::FailedFairings(_)));
+/// ```
+async fn compile_blocking<F>(
+    path: Option<&Path>,
+    timeout: Option<Duration>,
+    compile: F,
+) -> Result<String, SassError>
+where
+    F: FnOnce() -> Result<String, SassError> + Send + 'static,
+{
+    use rocket::tokio::{sync::oneshot, time};
+    use std::panic::{self, AssertUnwindSafe};
+
+    let (sender, receiver) = oneshot::channel();
+    std::thread::spawn(move || {
+        let _ = sender.send(panic::catch_unwind(AssertUnwindSafe(compile)));
+    });
+
+    let compiled = match timeout {
+        Some(timeout) => {
+            time::timeout(timeout, receiver)
+                .await
+                .map_err(|_| SassError::TimedOut {
+                    path: path.map(Path::to_path_buf),
+                    timeout,
+                })?
+        }
+        None => receiver.await,
+    };
+    compiled
+        .expect("compilation thread always reports back")
+        .unwrap_or_else(|payload| {
+            let message = payload
+                .downcast_ref::<&str>()
+                .map(|message| message.to_string())
+                .or_else(|| payload.downcast_ref::<String>().cloned())
+                .unwrap_or_else(|| "unknown panic payload".to_owned());
+            Err(SassError::CompileFailed {
+                path: path.map(Path::to_path_buf),
+                file: None,
+                line: None,
+                column: None,
+                message: format!("The sass compiler panicked: {}", message),
+            })
+        })
+}
+
+/// Normalizes `path`, resolving symlinks unless `sass_follow_symlinks` is unset, and checks it
+/// can be opened, retrying up to `sass_read_retries` times on failure.
+async fn resolve_sheet_path(path: &Path, config: &SassConfig) -> io::Result<PathBuf> {
+    let mut attempt = 0;
+    loop {
+        let resolved = if config.follow_symlinks {
+            path.normalize().map(|path| path.into_path_buf())
+        } else {
+            Ok(path.to_path_buf())
+        };
+        let resolved = resolved.and_then(|path| {
+            File::open(&path)?;
+            Ok(path)
+        });
+        match resolved {
+            Err(e) if attempt < config.read_retries => {
+                let backoff = config.read_retry_backoff(attempt);
+                warn!(
+                    "Couldn't read sass sheet file '{}': {}. Retrying in {:?}...",
+                    path.display(),
+                    e,
+                    backoff
+                );
+                rocket::tokio::time::sleep(backoff).await;
+                attempt += 1;
+            }
+            resolved => return resolved,
+        }
+    }
+}
+
+/// Aborts launch when a route uses the sheet but none is managed, e.g. because the fairing
+/// wasn't attached:
+/// ```rust
+/// use rocket::{get, routes};
+/// use rocket_sass_fairing::SassSheet;
+///
+/// #[get("/assets/style.css")]
+/// async fn style(sheet: &SassSheet) -> &SassSheet {
+///     sheet
+/// }
+///
+/// let rocket = rocket::build().mount("/", routes![style]);
+/// # use rocket::{error::ErrorKind, local::blocking::Client};
+/// # let error = Client::tracked(rocket).unwrap_err();
+/// # assert!(matches!(error.kind(), ErrorKind::SentinelAborts(_)));
+/// ```
+impl Sentinel for SassSheet {
+    fn abort(rocket: &Rocket<Ignite>) -> bool {
+        if rocket.state::<SassSheet>().is_some() {
+            return false;
+        }
+        error!("A route uses the sass sheet, but none is managed.");
+        info_!("Attach `SassSheet::fairing()`, or manage a `SassSheet`.");
+        true
     }
 }
 
@@ -128,11 +1400,28 @@ impl<'r> FromRequest<'r> for &'r SassSheet {
     }
 }
 impl<'r, 'o: 'r> Responder<'r, 'o> for &'o SassSheet {
-    fn respond_to(self, req: &'r Request<'_>) -> response::Result<'o> {
-        let content: &str = self.content.as_ref();
-        Response::build_from(content.respond_to(req)?)
+    fn respond_to(self, _: &'r Request<'_>) -> response::Result<'o> {
+        let current = self.current();
+        let body = SharedBody(current.content.clone());
+        // Revalidated on every use, so browsers drop it as soon as maintenance is over.
+        let cache_control = match current.0.in_maintenance {
+            true => "no-cache".to_owned(),
+            false => current.cache_control.clone(),
+        };
+        Response::build()
             .header(ContentType::CSS)
-            .raw_header("Cache-control", format!("max-age={}", self.cache_max_age))
+            .raw_header("Cache-control", cache_control)
+            .raw_header("X-Asset-Version", current.version.clone())
+            .sized_body(body.0.len(), Cursor::new(body))
             .ok()
     }
 }
+
+/// The compiled content, shared with the sheet rather than copied into each response.
+struct SharedBody(Arc<str>);
+
+impl AsRef<[u8]> for SharedBody {
+    fn as_ref(&self) -> &[u8] {
+        self.0.as_bytes()
+    }
+}
@@ -0,0 +1,215 @@
+use crate::{imports, scan};
+use rocket::serde::Serialize;
+use std::{collections::BTreeMap, fs, io, path::Path};
+
+/// The variables, mixins and functions a sheet defines and references, for finding dead design
+/// tokens.
+///
+/// Built from sources rather than compiled CSS, which keeps none of them. Names are compared the
+/// way Sass does, with `-` and `_` equivalent. Members of other modules (`math.$pi`,
+/// `@include lib.reset`) are left out, and so are functions that aren't defined in the sheet,
+/// as those can't be told apart from plain CSS functions.
+/// ```rust
+/// use rocket_sass_fairing::SheetReport;
+///
+/// let report = SheetReport::from_source(
+///     "$accent: red; $unused: blue;
+///      @mixin big { font-size: 2em; }
+///      a { color: $accent; @include big; }",
+/// );
+/// let unused: Vec<_> = report.unused().map(|symbol| &symbol.name).collect();
+/// assert_eq!(unused, ["unused"]);
+/// assert_eq!(report.mixins[0].references, 1);
+/// # let report = SheetReport::from_source(
+/// #     "@use 'sass:math';
+/// #      @function double($n) { @return $n * 2; }
+/// #      @mixin pad($size: 1px) { padding: double($size); }
+/// #      // $commented: 1;
+/// #      a { @include pad($size: math.$pi); content: '$quoted'; width: calc(1px); }",
+/// # );
+/// # assert_eq!(report.unused().count(), 0);
+/// # let names = |symbols: &[rocket_sass_fairing::SassSymbol]| {
+/// #     symbols.iter().map(|symbol| symbol.name.clone()).collect::<Vec<_>>()
+/// # };
+/// # assert_eq!(names(&report.variables), ["n", "size"]);
+/// # assert_eq!(names(&report.functions), ["double"]);
+/// # assert_eq!(report.functions[0].references, 1);
+/// # assert_eq!(report.mixins[0].references, 1);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct SheetReport {
+    pub variables: Vec<SassSymbol>,
+    pub mixins: Vec<SassSymbol>,
+    pub functions: Vec<SassSymbol>,
+}
+
+/// A name found by [`SheetReport`], sorted by name within its kind.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct SassSymbol {
+    pub name: String,
+    /// How many times it's defined; zero for variables coming from elsewhere.
+    pub definitions: usize,
+    /// How many times it's used.
+    pub references: usize,
+}
+
+#[derive(Default)]
+struct Counts {
+    variables: BTreeMap<String, (usize, usize)>,
+    mixins: BTreeMap<String, (usize, usize)>,
+    functions: BTreeMap<String, usize>,
+    calls: BTreeMap<String, usize>,
+}
+
+impl SheetReport {
+    /// Analyzes a single source, without following its imports.
+    pub fn from_source(source: &str) -> Self {
+        let mut counts = Counts::default();
+        scan(source, &mut counts);
+        counts.into()
+    }
+
+    /// Analyzes the sheet at `path` along with every file it imports.
+    pub fn from_path<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let mut counts = Counts::default();
+        for path in imports::reachable(path.as_ref()) {
+            scan(&fs::read_to_string(path)?, &mut counts);
+        }
+        Ok(counts.into())
+    }
+
+    /// The variables, mixins and functions defined but never referenced.
+    pub fn unused(&self) -> impl Iterator<Item = &SassSymbol> {
+        self.variables
+            .iter()
+            .chain(&self.mixins)
+            .chain(&self.functions)
+            .filter(|symbol| symbol.definitions > 0 && symbol.references == 0)
+    }
+}
+
+impl From<Counts> for SheetReport {
+    fn from(counts: Counts) -> Self {
+        let Counts {
+            variables,
+            mixins,
+            functions,
+            calls,
+        } = counts;
+        let symbols = |map: BTreeMap<String, (usize, usize)>| {
+            map.into_iter()
+                .map(|(name, (definitions, references))| SassSymbol {
+                    name,
+                    definitions,
+                    references,
+                })
+                .collect()
+        };
+        let functions = functions
+            .into_iter()
+            .map(|(name, definitions)| {
+                let references = calls.get(&name).copied().unwrap_or_default();
+                (name, (definitions, references))
+            })
+            .collect();
+        SheetReport {
+            variables: symbols(variables),
+            mixins: symbols(mixins),
+            functions: symbols(functions),
+        }
+    }
+}
+
+/// Counts the definitions and references in `source`, skipping comments and strings.
+fn scan(source: &str, counts: &mut Counts) {
+    let mut chars = source.char_indices().peekable();
+    let mut depth = 0;
+    let mut previous = ' ';
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '"' | '\'' => scan::skip_string(&mut chars, c),
+            '/' => {
+                scan::skip_comment(&mut chars, true);
+            }
+            '(' => depth += 1,
+            ')' => depth -= 1,
+            '$' if previous != '.' => {
+                let name = ident(&source[i + 1..]);
+                skip(&mut chars, name.len());
+                let rest = source[i + 1 + name.len()..].trim_start();
+                let entry = counts.variables.entry(normalize(name)).or_default();
+                match (depth, rest.starts_with(':')) {
+                    (0, true) => entry.0 += 1,
+                    // A parameter or keyword argument.
+                    (_, true) => {}
+                    (_, false) => entry.1 += 1,
+                }
+            }
+            '@' => {
+                let rule = ident(&source[i + 1..]);
+                skip(&mut chars, rule.len());
+                let after = &source[i + 1 + rule.len()..];
+                let name = ident(after.trim_start());
+                let namespaced = after.trim_start()[name.len()..].starts_with('.');
+                if name.is_empty() || namespaced {
+                    continue;
+                }
+                skip(
+                    &mut chars,
+                    after.len() - after.trim_start().len() + name.len(),
+                );
+                match rule {
+                    "mixin" => counts.mixins.entry(normalize(name)).or_default().0 += 1,
+                    "include" => counts.mixins.entry(normalize(name)).or_default().1 += 1,
+                    "function" => *counts.functions.entry(normalize(name)).or_default() += 1,
+                    _ => {}
+                }
+            }
+            c if is_ident_start(c) && !is_ident_char(previous) && previous != '.' => {
+                let name = ident(&source[i..]);
+                skip(&mut chars, name.len() - c.len_utf8());
+                if source[i + name.len()..].starts_with('(') {
+                    *counts.calls.entry(normalize(name)).or_default() += 1;
+                }
+                previous = name.chars().last().unwrap_or(c);
+                continue;
+            }
+            _ => {}
+        }
+        previous = c;
+    }
+}
+
+/// Advances `chars` past the next `bytes` bytes.
+fn skip(chars: &mut std::iter::Peekable<impl Iterator<Item = (usize, char)>>, bytes: usize) {
+    let mut skipped = 0;
+    while skipped < bytes {
+        match chars.next() {
+            Some((_, c)) => skipped += c.len_utf8(),
+            None => break,
+        }
+    }
+}
+
+/// The identifier `source` starts with, if any.
+fn ident(source: &str) -> &str {
+    let end = source
+        .char_indices()
+        .find(|&(_, c)| !is_ident_char(c))
+        .map_or(source.len(), |(i, _)| i);
+    &source[..end]
+}
+
+fn is_ident_start(c: char) -> bool {
+    c.is_alphabetic() || c == '_' || c == '-'
+}
+
+fn is_ident_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_' || c == '-' || !c.is_ascii()
+}
+
+fn normalize(name: &str) -> String {
+    name.replace('_', "-")
+}
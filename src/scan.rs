@@ -0,0 +1,40 @@
+use std::iter::Peekable;
+
+/// Advances `chars` past the end of the string opened by `quote`, honoring escapes.
+pub(crate) fn skip_string(chars: &mut impl Iterator<Item = (usize, char)>, quote: char) {
+    let mut escaped = false;
+    for (_, next) in chars {
+        match next {
+            _ if escaped => escaped = false,
+            '\\' => escaped = true,
+            next if next == quote => break,
+            _ => {}
+        }
+    }
+}
+
+/// Advances `chars`, just past a `/`, to the end of the comment it opens if any. Returns whether
+/// there was one. `//` comments are Sass only, so plain CSS leaves `line_comments` unset.
+pub(crate) fn skip_comment(
+    chars: &mut Peekable<impl Iterator<Item = (usize, char)>>,
+    line_comments: bool,
+) -> bool {
+    match chars.peek().map(|&(_, next)| next) {
+        Some('/') if line_comments => {
+            chars.by_ref().find(|&(_, next)| next == '\n');
+            true
+        }
+        Some('*') => {
+            chars.next();
+            let mut previous = ' ';
+            for (_, next) in chars.by_ref() {
+                if previous == '*' && next == '/' {
+                    break;
+                }
+                previous = next;
+            }
+            true
+        }
+        _ => false,
+    }
+}
@@ -0,0 +1,163 @@
+use crate::SassSheet;
+use rocket::{
+    data::Data,
+    http::{
+        uri::{fmt::Path, Segments},
+        Method,
+    },
+    route::{Handler, Outcome, Route},
+    Request,
+};
+
+/// Serves the managed [`SassSheet`] when mounted, the way [`FileServer`] serves files.
+///
+/// Built with [`SassSheet::mountable`]. Paths match exactly unless [`trailing_slash`] or
+/// [`case_insensitive`] are set. Requests forward when no sheet is managed, e.g. when the
+/// fairing isn't attached.
+///
+/// [`trailing_slash`]: SassSheetServer::trailing_slash
+/// [`case_insensitive`]: SassSheetServer::case_insensitive
+///
+/// [`FileServer`]: rocket::fs::FileServer
+#[derive(Debug, Clone)]
+pub struct SassSheetServer {
+    path: String,
+    trailing_slash: bool,
+    case_insensitive: bool,
+}
+
+impl SassSheetServer {
+    /// Also serves the sheet when its path is requested with a trailing slash.
+    /// ```rust
+    /// use rocket_sass_fairing::SassSheet;
+    ///
+    /// let sheet = SassSheet::mountable("style.css").trailing_slash(true);
+    /// let rocket = rocket::build().attach(SassSheet::fairing()).mount("/assets", sheet);
+    /// # use rocket::{http::Status, local::blocking::Client};
+    /// # let client = Client::tracked(rocket).unwrap();
+    /// # assert_eq!(client.get("/assets/style.css/").dispatch().status(), Status::Ok);
+    /// # assert_eq!(client.get("/assets/style.css").dispatch().status(), Status::Ok);
+    /// # assert_eq!(client.get("/assets/STYLE.css").dispatch().status(), Status::NotFound);
+    /// ```
+    pub fn trailing_slash(mut self, allow: bool) -> Self {
+        self.trailing_slash = allow;
+        self
+    }
+
+    /// Matches the sheet's path ignoring ASCII case, e.g. `/ASSETS/Style.CSS`.
+    ///
+    /// Only the part of the path given to [`SassSheet::mountable`] is affected; the base it's
+    /// mounted at is still matched by Rocket, case-sensitively.
+    /// ```rust
+    /// use rocket_sass_fairing::SassSheet;
+    ///
+    /// let sheet = SassSheet::mountable("style.css").case_insensitive(true);
+    /// let rocket = rocket::build().attach(SassSheet::fairing()).mount("/assets", sheet);
+    /// # use rocket::{http::Status, local::blocking::Client};
+    /// # let client = Client::tracked(rocket).unwrap();
+    /// # assert_eq!(client.get("/assets/Style.CSS").dispatch().status(), Status::Ok);
+    /// # assert_eq!(client.get("/assets/style.css/").dispatch().status(), Status::NotFound);
+    /// # assert_eq!(client.get("/assets/other.css").dispatch().status(), Status::NotFound);
+    /// ```
+    pub fn case_insensitive(mut self, enable: bool) -> Self {
+        self.case_insensitive = enable;
+        self
+    }
+
+    /// Whether the request path, split in `segments` relative to the mount base, names the
+    /// sheet. Rocket drops the empty segment a trailing slash leaves, hence `trailing_slash`.
+    fn matches(&self, segments: &[&str], trailing_slash: bool) -> bool {
+        if trailing_slash && !self.trailing_slash {
+            return false;
+        }
+        // An empty path names the mount base itself, which Rocket splits into no segments at all.
+        let expected: Vec<_> = match self.path.is_empty() {
+            true => Vec::new(),
+            false => self.path.split('/').collect(),
+        };
+        expected.len() == segments.len()
+            && expected.iter().zip(segments).all(|(expected, segment)| {
+                match self.case_insensitive {
+                    true => expected.eq_ignore_ascii_case(segment),
+                    false => expected == segment,
+                }
+            })
+    }
+}
+
+impl SassSheet {
+    /// A mountable serving the sheet at `path`, relative to the base it's mounted at.
+    /// ```rust
+    /// use rocket_sass_fairing::SassSheet;
+    ///
+    /// let rocket = rocket::build()
+    ///     .attach(SassSheet::fairing())
+    ///     .mount("/assets", SassSheet::mountable("style.css"));
+    /// # use rocket::local::blocking::Client;
+    /// # let client = Client::tracked(rocket).unwrap();
+    /// # let response = client.get("/assets/style.css").dispatch();
+    /// # assert_eq!(response.content_type(), Some(rocket::http::ContentType::CSS));
+    /// # assert_eq!(client.get("/style.css").dispatch().status(), rocket::http::Status::NotFound);
+    /// # assert_eq!(client.get("/assets/style.css/").dispatch().status(), rocket::http::Status::NotFound);
+    /// ```
+    ///
+    /// An empty `path`, or `/`, serves the sheet at the mount base itself:
+    /// ```rust
+    /// use rocket_sass_fairing::SassSheet;
+    ///
+    /// let rocket = rocket::build()
+    ///     .attach(SassSheet::fairing())
+    ///     .mount("/style.css", SassSheet::mountable("/"));
+    /// # use rocket::{http::Status, local::blocking::Client};
+    /// # let client = Client::tracked(rocket).unwrap();
+    /// # assert_eq!(client.get("/style.css").dispatch().status(), Status::Ok);
+    /// # assert_eq!(client.get("/style.css/").dispatch().status(), Status::NotFound);
+    /// # assert_eq!(client.get("/style.css/a").dispatch().status(), Status::NotFound);
+    /// # let rocket = rocket::build().attach(SassSheet::fairing());
+    /// # let sheet = SassSheet::mountable("").trailing_slash(true).case_insensitive(true);
+    /// # let client = Client::tracked(rocket.mount("/style.css", sheet)).unwrap();
+    /// # assert_eq!(client.get("/style.css").dispatch().status(), Status::Ok);
+    /// # assert_eq!(client.get("/style.css/").dispatch().status(), Status::Ok);
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Mounting panics if `path` isn't a valid, static route path.
+    pub fn mountable(path: &str) -> SassSheetServer {
+        SassSheetServer {
+            path: path.trim_start_matches('/').to_owned(),
+            trailing_slash: false,
+            case_insensitive: false,
+        }
+    }
+}
+
+impl From<SassSheetServer> for Vec<Route> {
+    fn from(server: SassSheetServer) -> Self {
+        // Rocket's router only matches static paths exactly, so lenient matching is done by the
+        // handler, behind a catch-all route.
+        let uri = match server.trailing_slash || server.case_insensitive {
+            true => "/<path..>".to_owned(),
+            false => format!("/{}", server.path),
+        };
+        let mut route = Route::new(Method::Get, &uri, server);
+        route.name = Some("SassSheet".into());
+        vec![route]
+    }
+}
+
+#[rocket::async_trait]
+impl Handler for SassSheetServer {
+    async fn handle<'r>(&self, req: &'r Request<'_>, data: Data<'r>) -> Outcome<'r> {
+        let segments: Vec<_> = match req.segments::<Segments<'_, Path>>(0..) {
+            Ok(segments) => segments.collect(),
+            Err(never) => match never {},
+        };
+        match req.rocket().state::<SassSheet>() {
+            Some(sheet) if self.matches(&segments, req.uri().path().ends_with('/')) => {
+                Outcome::from(req, sheet)
+            }
+            _ => Outcome::forward(data),
+        }
+    }
+}
@@ -0,0 +1,124 @@
+use crate::scan;
+use std::{collections::HashMap, ops::Range};
+
+/// Rewrites every `url(...)` reference in `css` found in `manifest` to its mapped value.
+///
+/// Quoted and unquoted references are supported, and keep their quoting. `data:` URIs,
+/// references missing from the manifest, and `url(` text inside strings or comments are left
+/// untouched.
+/// ```rust
+/// use rocket_sass_fairing::rewrite_urls;
+/// use std::collections::HashMap;
+///
+/// let mut manifest = HashMap::new();
+/// manifest.insert("/img/logo.png".to_owned(), "/img/logo.3f2a9c.png".to_owned());
+///
+/// assert_eq!(
+///     rewrite_urls("a{background:url('/img/logo.png')}", &manifest),
+///     "a{background:url('/img/logo.3f2a9c.png')}"
+/// );
+/// assert_eq!(
+///     rewrite_urls("a{background:url( /img/logo.png )}", &manifest),
+///     "a{background:url( /img/logo.3f2a9c.png )}"
+/// );
+/// assert_eq!(
+///     rewrite_urls("a{background:url(data:image/png;base64,AAAA)}", &manifest),
+///     "a{background:url(data:image/png;base64,AAAA)}"
+/// );
+/// # assert_eq!(
+/// #     rewrite_urls("a{content:'url(/img/logo.png)'}/* url(/img/logo.png) */", &manifest),
+/// #     "a{content:'url(/img/logo.png)'}/* url(/img/logo.png) */"
+/// # );
+/// # assert_eq!(
+/// #     rewrite_urls("a{b:url(\"/img/logo.png\")}b{c:URL(/img/logo.png)}", &manifest),
+/// #     "a{b:url(\"/img/logo.3f2a9c.png\")}b{c:URL(/img/logo.3f2a9c.png)}"
+/// # );
+/// ```
+pub fn rewrite_urls(css: &str, manifest: &HashMap<String, String>) -> String {
+    let mut rewritten = String::with_capacity(css.len());
+    let mut copied = 0;
+
+    let mut chars = css.char_indices().peekable();
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '"' | '\'' => scan::skip_string(&mut chars, c),
+            '/' => {
+                scan::skip_comment(&mut chars, false);
+            }
+            _ if is_url_token(css, i) => {
+                let (range, end) = url_reference(css, i + "url(".len());
+                let reference = &css[range.clone()];
+                rewritten.push_str(&css[copied..range.start]);
+                match manifest.get(reference) {
+                    Some(replacement) if !is_data_uri(reference) => rewritten.push_str(replacement),
+                    _ => rewritten.push_str(reference),
+                }
+                copied = range.end;
+                while chars.peek().is_some_and(|&(j, _)| j < end) {
+                    chars.next();
+                }
+            }
+            _ => {}
+        }
+    }
+
+    rewritten.push_str(&css[copied..]);
+    rewritten
+}
+
+/// Whether a `url(` function token starts at byte `i`, rather than an identifier merely ending
+/// in `url`.
+fn is_url_token(css: &str, i: usize) -> bool {
+    let bytes = css.as_bytes();
+    bytes
+        .get(i..i + "url(".len())
+        .is_some_and(|token| token.eq_ignore_ascii_case(b"url("))
+        && (i == 0 || !is_ident_byte(bytes[i - 1]))
+}
+
+/// Byte range of the reference in the `url()` argument starting at `start`, and the offset past
+/// it and its closing quote, if any.
+fn url_reference(css: &str, start: usize) -> (Range<usize>, usize) {
+    let value = css[start..].trim_start();
+    let value_start = css.len() - value.len();
+    match value.chars().next().filter(|&c| c == '"' || c == '\'') {
+        Some(quote) => {
+            let reference_start = value_start + 1;
+            match find_closing_quote(&css[reference_start..], quote) {
+                Some(end) => (
+                    reference_start..reference_start + end,
+                    reference_start + end + 1,
+                ),
+                None => (reference_start..css.len(), css.len()),
+            }
+        }
+        None => {
+            let end = value.find(')').unwrap_or(value.len());
+            let reference_end = value_start + value[..end].trim_end().len();
+            (value_start..reference_end, reference_end)
+        }
+    }
+}
+
+fn find_closing_quote(value: &str, quote: char) -> Option<usize> {
+    let mut escaped = false;
+    for (i, c) in value.char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' => escaped = true,
+            c if c == quote => return Some(i),
+            _ => {}
+        }
+    }
+    None
+}
+
+fn is_ident_byte(byte: u8) -> bool {
+    byte.is_ascii_alphanumeric() || byte == b'-' || byte == b'_' || !byte.is_ascii()
+}
+
+fn is_data_uri(reference: &str) -> bool {
+    reference
+        .get(.."data:".len())
+        .is_some_and(|scheme| scheme.eq_ignore_ascii_case("data:"))
+}